@@ -0,0 +1,345 @@
+use alloc::{sync::Arc, vec::Vec};
+use crate::{builder::AhoCorasickBuilder, byte_classes::ByteClasses, compile::Compilation, double_array::DoubleArray, lazy_dfa::{LazyDfaCache, LazyDfaStats}, prefilter::Prefilter, table::Table, trie::Trie};
+
+/// Identifier of an automaton state : an index into the state table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct StateId(pub(crate) u32);
+
+impl StateId {
+    pub(crate) const ROOT: StateId = StateId(0);
+    /// Marks a missing transition (or a missing link) in the tables
+    pub(crate) const FAIL: StateId = StateId(u32::MAX);
+
+    #[inline(always)]
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Contiguous Aho-Corasick automaton
+/// All the states live in a single flat table and reference each other by their StateId. Transitions are stored in shared pools,
+/// as a dense row (one entry per byte class) for states with a large fan-out, or as a sparse row (sorted classes with their targets) otherwise.
+/// With the double-array backend, the rows are instead interleaved into the dense pool, a check table telling which state owns each entry.
+/// Input bytes are first remapped to their class : bytes behaving the same in every state share a class, which keeps the rows short.
+/// Once built, it is never modified anymore, which makes it Send + Sync : it is meant to be shared behind an Arc by any number of streams.
+/// The tables are either owned, or read in place from the bytes of a serialized automaton (see AhoCorasick::from_shared_bytes)
+///
+/// Besides the streams, it can be walked directly : start_state, next_state, is_match, match_len and replacement give a low-level API over
+/// copyable StateIds, e.g. to match from a custom parser. The automaton holds no state of its own, so any number of walks can share it
+#[derive(Debug)]
+pub struct AcAutomaton {
+    pub(crate) states: Table<State>, // Root is always the first state, followed by the other states in breadth-first order
+    pub(crate) byte_classes: ByteClasses,
+    pub(crate) dense: Table<StateId>, // Dense rows, alphabet_len entries each. StateId::FAIL when there is no transition
+    pub(crate) check: Table<StateId>, // Owner of each entry of the dense pool. Empty unless built with the double-array backend
+    pub(crate) sparse_classes: Table<u8>, // Sparse rows : transition byte classes, sorted within a row
+    pub(crate) sparse_next: Table<StateId>, // Sparse rows : transition targets, matching sparse_classes
+    pub(crate) replacement_bounds: Table<[u32; 2]>, // Start and end in replacement_bytes, for each word
+    pub(crate) replacement_bytes: Table<u8>, // All the replacements, concatenated
+    pub(crate) dfa: Table<StateId>, // Fully resolved transitions, alphabet_len entries per state. Empty unless built in DFA mode
+    pub(crate) states_removed: u32, // Amount of states merged away by minimization
+    pub(crate) lazy_dfa_cache: u32, // Size of the lazy DFA cache of each cursor. 0 if disabled
+    pub(crate) flags: u32, // Combination of the FLAG_* options
+    pub(crate) prefilter: Prefilter, // Derived from the root row when the automaton is built or loaded, so it is not serialized
+}
+
+/// One entry of the state table
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct State {
+    pub(crate) depth: u32,
+    pub(crate) suffix_link: StateId, // Root links to itself
+    pub(crate) output_link: StateId, // StateId::FAIL if there is no output link
+    pub(crate) word: u32, // NOT_WORD, or index of the word in replacement_bounds
+    pub(crate) row_start: u32, // Start of the transitions row, in dense or in sparse pools
+    pub(crate) row_len: u32, // Amount of sparse transitions, DENSE_ROW, or DOUBLE_ARRAY_ROW
+}
+
+/// Cursor walking over a shared AcAutomaton. This is the only per-stream state : each reader or writer owns its own cursor,
+/// while the automaton itself is shared between all of them
+#[derive(Debug, Clone)]
+pub struct AcCursor {
+    automaton: Arc<AcAutomaton>,
+    state: StateId,
+    cache: Option<LazyDfaCache>, // Only in lazy DFA mode
+}
+
+impl AcAutomaton {
+    pub(crate) const NOT_WORD: u32 = u32::MAX;
+    pub(crate) const DENSE_ROW: u32 = u32::MAX;
+    /// Row interleaved in the dense pool : the entry of a class belongs to the state only if the check table says so
+    pub(crate) const DOUBLE_ARRAY_ROW: u32 = u32::MAX - 1;
+    /// Replacement bounds of a word which has no replacement (protected word)
+    pub(crate) const NO_REPLACEMENT: [u32; 2] = [u32::MAX; 2];
+    /// States having transitions for at least 1/DENSE_RATIO of the byte classes get a dense row (see Rows)
+    const DENSE_RATIO: usize = 8;
+    /// The streams walk the automaton over every byte, without skipping to the next byte which can start a match
+    pub(crate) const FLAG_NO_PREFILTER: u32 = 1;
+
+    /// Compiles the trie (with its links already calculated) into the contiguous representation, all the passes at once (see Compilation)
+    pub(crate) fn from_trie(trie: Trie, options: &AhoCorasickBuilder) -> Self {
+        Compilation::new(trie, options).finish()
+    }
+
+    /// Builds the prefilter, unless it was disabled. The tables must be complete and valid
+    pub(crate) fn init_prefilter(&mut self) {
+        if self.flags & Self::FLAG_NO_PREFILTER == 0 {
+            self.prefilter = Prefilter::new(self);
+        }
+    }
+
+    /// Follows the direct (trie) transition of the state for this byte class, if there is one
+    #[inline(always)]
+    pub(crate) fn goto(&self, id: StateId, class: u8) -> Option<StateId> {
+        let state = &self.states[id.index()];
+        let row_start = state.row_start as usize;
+        if state.row_len == Self::DENSE_ROW {
+            let next = self.dense[row_start + class as usize];
+            (next != StateId::FAIL).then_some(next)
+        } else if state.row_len == Self::DOUBLE_ARRAY_ROW {
+            let position = row_start + class as usize;
+            (self.check[position] == id).then(|| self.dense[position])
+        } else {
+            let row_end = row_start + state.row_len as usize;
+            self.sparse_classes[row_start..row_end].iter()
+                .position(|val| *val == class)
+                .map(|position| self.sparse_next[row_start + position])
+        }
+    }
+
+    /// State of the automaton before any input, and after a match has been handled : the streams go back to it after every word found
+    #[inline(always)]
+    pub fn start_state(&self) -> StateId {
+        StateId::ROOT
+    }
+
+    /// Finds the state following the given one with the next input byte : a single lookup in DFA mode, otherwise by following suffix links
+    /// until a state having a transition for this byte is found. The automaton is never modified, so any number of states can be walked
+    /// over it at once, from any thread. The state must come from this automaton : a state of another automaton is not detected, and gives
+    /// an unrelated state of this one, or panics if it is out of the range of its states
+    #[inline(always)]
+    pub fn next_state(&self, mut state: StateId, byte: u8) -> StateId {
+        let class = self.byte_classes.get(byte);
+        if !self.dfa.is_empty() {
+            return self.dfa[state.index() * self.byte_classes.alphabet_len() + class as usize];
+        }
+        loop {
+            if let Some(next) = self.goto(state, class) {
+                return next;
+            }
+            if state == StateId::ROOT {
+                // Root is the only state not having a suffix link
+                return state;
+            }
+            state = self.states[state.index()].suffix_link;
+        }
+    }
+
+    /// Whether the last match_len bytes of the input form a word of the dictionary. Only the state itself counts, not its suffixes :
+    /// this is how the streams decide, replacing as soon as a word is found. Root (no input matched) is never a match
+    #[inline(always)]
+    pub fn is_match(&self, state: StateId) -> bool {
+        state != StateId::ROOT && self.is_word(state)
+    }
+
+    /// Whether the state ends a word of the dictionary. Unlike is_match, this includes root when an empty word was given to the builder
+    #[inline(always)]
+    pub(crate) fn is_word(&self, state: StateId) -> bool {
+        self.states[state.index()].word != Self::NOT_WORD
+    }
+
+    /// Amount of last input bytes matched by the state : they are the beginning of a word (the whole word if is_match), so the streams
+    /// hold them back until it is known whether they are replaced. The bytes before them can not be part of a match anymore
+    #[inline(always)]
+    pub fn match_len(&self, state: StateId) -> usize {
+        self.depth(state)
+    }
+
+    #[inline(always)]
+    pub(crate) fn depth(&self, state: StateId) -> usize {
+        self.states[state.index()].depth as usize
+    }
+
+    /// Replacement of the word matched by the state. None if the state is not a match, or if the word has no replacement (protected word) :
+    /// when is_match, the matched bytes are then kept as is. It is borrowed from the automaton, so no copy is made
+    #[inline(always)]
+    pub fn replacement(&self, state: StateId) -> Option<&[u8]> {
+        let word = self.states[state.index()].word;
+        if word == Self::NOT_WORD {
+            return None;
+        }
+        match self.replacement_bounds[word as usize] {
+            Self::NO_REPLACEMENT => None,
+            [start, end] => Some(&self.replacement_bytes[start as usize..end as usize])
+        }
+    }
+
+    /// Direct (trie) transitions of the state, by byte class, sorted by class
+    pub(crate) fn row(&self, id: StateId) -> Vec<(u8, StateId)> {
+        let state = &self.states[id.index()];
+        let row_start = state.row_start as usize;
+        if state.row_len == Self::DENSE_ROW {
+            self.dense[row_start..row_start + self.byte_classes.alphabet_len()].iter().enumerate()
+                .filter(|(_, next)| **next != StateId::FAIL)
+                .map(|(class, next)| (class as u8, *next))
+                .collect()
+        } else if state.row_len == Self::DOUBLE_ARRAY_ROW {
+            (0..self.byte_classes.alphabet_len())
+                .filter(|class| self.check[row_start + class] == id)
+                .map(|class| (class as u8, self.dense[row_start + class]))
+                .collect()
+        } else {
+            let row_end = row_start + state.row_len as usize;
+            self.sparse_classes[row_start..row_end].iter().copied()
+                .zip(self.sparse_next[row_start..row_end].iter().copied())
+                .collect()
+        }
+    }
+
+    /// Direct (trie) transitions of the state, sorted by byte
+    pub(crate) fn transitions(&self, id: StateId) -> Vec<(u8, StateId)> {
+        let mut transitions: Vec<(u8, StateId)> = self.row(id).into_iter()
+            .flat_map(|(class, next)| self.byte_classes.bytes(class).map(move |val| (val, next)))
+            .collect();
+        transitions.sort_unstable();
+        transitions
+    }
+}
+
+/// Transitions pools filled row after row, with the layout chosen by the options : dense and sparse rows, or the double array
+pub(crate) struct Rows {
+    alphabet_len: usize,
+    double_array: Option<DoubleArray>,
+    dense: Vec<StateId>,
+    sparse_classes: Vec<u8>,
+    sparse_next: Vec<StateId>,
+}
+
+impl Rows {
+    /// Pools for states having the given amounts of transitions, in state order : the dense and sparse pools are allocated once,
+    /// at their final size
+    pub(crate) fn new(double_array: bool, alphabet_len: usize, row_lens: impl Iterator<Item = usize>) -> Self {
+        let mut rows = Self {
+            alphabet_len,
+            double_array: double_array.then(DoubleArray::default),
+            dense: Vec::new(),
+            sparse_classes: Vec::new(),
+            sparse_next: Vec::new(),
+        };
+        if rows.double_array.is_none() {
+            let (mut dense_len, mut sparse_len) = (0, 0);
+            for (state, row_len) in row_lens.enumerate() {
+                if rows.is_dense(state, row_len) {
+                    dense_len += alphabet_len;
+                } else {
+                    sparse_len += row_len;
+                }
+            }
+            rows.dense.reserve_exact(dense_len);
+            rows.sparse_classes.reserve_exact(sparse_len);
+            rows.sparse_next.reserve_exact(sparse_len);
+        }
+        rows
+    }
+
+    /// States having transitions for at least 1/DENSE_RATIO of the byte classes get a dense row. Root always gets one, as it is the most visited state
+    fn is_dense(&self, state: usize, row_len: usize) -> bool {
+        state == StateId::ROOT.index() || row_len * AcAutomaton::DENSE_RATIO >= self.alphabet_len
+    }
+
+    /// Adds the row (sorted by class) of the state, the states coming in order. Returns the row_start and row_len of the state
+    pub(crate) fn push(&mut self, state: StateId, row: &[(u8, StateId)]) -> (u32, u32) {
+        if let Some(double_array) = &mut self.double_array {
+            return if state == StateId::ROOT {
+                (double_array.push_dense(state, row, self.alphabet_len) as u32, AcAutomaton::DENSE_ROW)
+            } else {
+                (double_array.place(state, row, self.alphabet_len) as u32, AcAutomaton::DOUBLE_ARRAY_ROW)
+            };
+        }
+        if self.is_dense(state.index(), row.len()) {
+            let row_start = self.dense.len();
+            self.dense.resize(row_start + self.alphabet_len, StateId::FAIL);
+            for (class, next) in row {
+                self.dense[row_start + *class as usize] = *next;
+            }
+            (row_start as u32, AcAutomaton::DENSE_ROW)
+        } else {
+            let row_start = self.sparse_classes.len();
+            for (class, next) in row.iter() {
+                self.sparse_classes.push(*class);
+                self.sparse_next.push(*next);
+            }
+            (row_start as u32, row.len() as u32)
+        }
+    }
+
+    /// The dense pool, the check table, and the sparse pools
+    pub(crate) fn finish(self) -> (Vec<StateId>, Vec<StateId>, Vec<u8>, Vec<StateId>) {
+        match self.double_array {
+            Some(double_array) => (double_array.next, double_array.check, self.sparse_classes, self.sparse_next),
+            None => (self.dense, Vec::new(), self.sparse_classes, self.sparse_next),
+        }
+    }
+}
+
+impl AcCursor {
+    /// New cursor, with the state pointing at root
+    pub fn new(automaton: Arc<AcAutomaton>) -> Self {
+        let cache = (automaton.lazy_dfa_cache != 0).then(|| LazyDfaCache::new(automaton.lazy_dfa_cache as usize));
+        Self {
+            automaton,
+            state: StateId::ROOT,
+            cache,
+        }
+    }
+
+    /// Advances the state
+    #[inline(always)]
+    pub fn next_state(&mut self, char: &u8) {
+        self.state = match &mut self.cache {
+            Some(cache) => cache.next_state(&self.automaton, self.state, *char),
+            None => self.automaton.next_state(self.state, *char)
+        }
+    }
+
+    /// Amount of leading bytes of the input which can be written as is, without walking the automaton over them :
+    /// at root, the bytes before the next one which can start a match. 0 if the state is not root, or if there is no prefilter
+    #[inline(always)]
+    pub fn skippable(&self, input: &[u8]) -> usize {
+        if self.state != StateId::ROOT {
+            return 0;
+        }
+        self.automaton.prefilter.skippable(input)
+    }
+
+    /// Hit and miss counters of the lazy DFA cache. None if the automaton was not built in lazy DFA mode
+    pub fn lazy_dfa_stats(&self) -> Option<LazyDfaStats> {
+        self.cache.as_ref().map(LazyDfaCache::stats)
+    }
+
+    /// Checks whether the current state is pointing at the root node
+    pub fn is_state_root(&self) -> bool {
+        self.state == StateId::ROOT
+    }
+
+    /// Checks whether the node that the state is pointing at is a dictionary word
+    pub fn is_state_word(&self) -> bool {
+        self.automaton.is_word(self.state)
+    }
+
+    /// Reset state to point at root
+    pub fn reset_state(&mut self) {
+        self.state = StateId::ROOT
+    }
+
+    /// Gives the current tree depth of the node pointed at by the state
+    pub fn state_depth(&self) -> usize {
+        self.automaton.depth(self.state)
+    }
+
+    /// Replacement of the word the state is pointing at, if any. It is borrowed from the shared automaton, so no copy is made
+    pub fn state_replacement(&self) -> Option<&[u8]> {
+        self.automaton.replacement(self.state)
+    }
+}
@@ -0,0 +1,141 @@
+use core::{fmt::Display, future::Future, pin::Pin, sync::atomic::{AtomicBool, Ordering}, task::{Context, Poll}};
+use alloc::{sync::Arc, vec::Vec};
+use crate::{AhoCorasick, builder::AhoCorasickBuilder, compile::Compilation, trie::{LinksWalk, TrieInsertion}};
+
+/// Amount of words inserted, of nodes linked, or of states compiled, between two yields to the executor
+const SLICE: usize = 4096;
+
+/// Step reached by build_async, given to its progress callback after each slice of work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildProgress {
+    /// Words inserted into the trie so far
+    Inserting { words: usize },
+    /// Depth levels of the trie whose suffix and output links are calculated, out of the depth of the trie (the longest word)
+    Linking { levels: usize, depth: usize },
+    /// All the links are calculated : the tables of the automaton are being compiled. States done so far in the current pass, out of
+    /// the states of the automaton. While minimizing, the states are the classes of equivalent states found so far
+    Compiling { pass: CompilePass, states: usize, total: usize },
+}
+
+/// Pass of the compilation of the tables, in the order they run. Only the passes enabled by the options run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompilePass {
+    /// Writing the transitions rows of the states
+    Rows,
+    /// Merging the equivalent states (with minimize), one round of refinement per slice
+    Minimize,
+    /// Placing the rows of the merged states into the double array (with minimize and double_array)
+    DoubleArray,
+    /// Resolving every transition of the DFA (with dfa)
+    Dfa,
+}
+
+/// Cancels the builds it was given to, from any task or thread. Clones share the same state
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The builds using this token stop at the end of their current slice of work, returning BuildCancelled
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned by build_async when its CancellationToken was cancelled. Everything built so far is dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildCancelled;
+
+impl Display for BuildCancelled {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "the build of the automaton was cancelled")
+    }
+}
+
+impl core::error::Error for BuildCancelled {}
+
+impl AhoCorasickBuilder {
+    /// Same as build_from_iter, without blocking the executor for the whole build : the words are inserted and the links calculated
+    /// in slices, yielding to the executor between them, so that the other tasks keep running even on a single-threaded executor.
+    /// The progress callback is called after each slice. The build stops with BuildCancelled at the first slice after cancellation.
+    /// The tables are compiled in slices too, except for the breadth-first renumbering of the trie, which runs as one step, and for
+    /// minimize, whose slices are whole rounds of refinement
+    pub async fn build_async<I, P>(&self, replacements: I, mut progress: P, cancellation: CancellationToken) -> Result<AhoCorasick, BuildCancelled>
+    where
+        I: IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+        P: FnMut(BuildProgress)
+    {
+        let checkpoint = |progress: &mut P, step: BuildProgress| {
+            if cancellation.is_cancelled() {
+                return Err(BuildCancelled);
+            }
+            progress(step);
+            Ok(())
+        };
+        // Same steps as build_from_iter, with a yield every slice
+        let mut insertion = TrieInsertion::new();
+        for (word, replacement) in replacements.into_iter() {
+            insertion.insert(word, replacement);
+            if insertion.words.is_multiple_of(SLICE) {
+                checkpoint(&mut progress, BuildProgress::Inserting { words: insertion.words })?;
+                YieldNow::default().await;
+            }
+        }
+        checkpoint(&mut progress, BuildProgress::Inserting { words: insertion.words })?;
+
+        let (mut trie, depth) = (insertion.trie, insertion.depth);
+        let mut walk = LinksWalk::new(&mut trie);
+        let mut levels = 0;
+        let mut linked: usize = 0;
+        while let Some(level) = walk.step(&mut trie) {
+            linked += 1;
+            // Reported when the walk reaches a deeper level, and every slice within a level
+            if level as usize > levels || linked.is_multiple_of(SLICE) {
+                levels = level as usize;
+                checkpoint(&mut progress, BuildProgress::Linking { levels, depth })?;
+                YieldNow::default().await;
+            }
+        }
+
+        // Renumbering the trie runs as one step : it is not started once cancelled
+        if cancellation.is_cancelled() {
+            return Err(BuildCancelled);
+        }
+        let mut compilation = Compilation::new(trie, self);
+        while let Some(step) = compilation.step(SLICE) {
+            checkpoint(&mut progress, step)?;
+            YieldNow::default().await;
+        }
+        Ok(AhoCorasick {
+            automaton: Arc::new(compilation.finish()),
+        })
+    }
+}
+
+/// Returns Pending once, after waking the task up : the executor runs the other ready tasks before polling it again
+#[derive(Default)]
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
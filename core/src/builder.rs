@@ -0,0 +1,118 @@
+use alloc::{sync::Arc, vec::Vec};
+use crate::{AhoCorasick, automaton::AcAutomaton, trie::{Trie, TrieInsertion}};
+
+/// Builder allowing to tune how the automaton is compiled. The default configuration is the one used by AhoCorasick::new
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AhoCorasickBuilder {
+    pub(crate) dfa: bool,
+    pub(crate) byte_classes: bool,
+    pub(crate) minimize: bool,
+    pub(crate) double_array: bool,
+    pub(crate) lazy_dfa_cache: usize,
+    pub(crate) prefilter: bool,
+}
+
+impl Default for AhoCorasickBuilder {
+    fn default() -> Self {
+        Self {
+            dfa: false,
+            byte_classes: true,
+            minimize: false,
+            double_array: false,
+            lazy_dfa_cache: 0,
+            prefilter: true,
+        }
+    }
+}
+
+impl AhoCorasickBuilder {
+    /// Largest lazy DFA cache, in entries. Each stream allocates its own cache, so a larger one would make every stream costly
+    pub const MAX_LAZY_DFA_CACHE: usize = 1 << 20;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When enabled, the transition of every (state, byte) pair is resolved at construction time, so advancing the state
+    /// is always a single table lookup, and suffix links are never followed while matching.
+    /// This requires one transition per state and per byte class, so it is best suited for small and frequently used dictionaries. Disabled by default
+    pub fn dfa(&mut self, yes: bool) -> &mut Self {
+        self.dfa = yes;
+        self
+    }
+
+    /// When enabled, bytes behaving the same in every state are grouped into classes, and the transition rows (dense and DFA)
+    /// only have one entry per class instead of one per byte value. Input bytes are remapped through a 256-entry table while matching.
+    /// Most dictionaries only use a few dozen distinct bytes, so this greatly reduces the memory usage. Enabled by default
+    pub fn byte_classes(&mut self, yes: bool) -> &mut Self {
+        self.byte_classes = yes;
+        self
+    }
+
+    /// When enabled, the streams skip over the bytes which can not start a match, using memchr when the words start with at most 3 distinct bytes,
+    /// or a byte set scan when their start bytes are rare in usual inputs. The skipped spans are copied to the output in bulk, which is
+    /// much faster than walking the automaton byte by byte when matches are scarce. The results are exactly the same. Enabled by default
+    pub fn prefilter(&mut self, yes: bool) -> &mut Self {
+        self.prefilter = yes;
+        self
+    }
+
+    /// Lazy DFA mode : each stream caches the transitions it resolves, up to the given amount of entries (rounded up to a power of two),
+    /// so that the suffix links of a (state, byte) pair are followed only the first time. Unlike the full DFA, the memory used does not depend
+    /// on the size of the dictionary : each entry takes 12 bytes per stream. Once the cache is full, new transitions evict the older ones.
+    /// The hit and miss counters are given by the readers and writers, to tune the size. Ignored in DFA mode. 0 (the default) disables it.
+    /// The size is capped to MAX_LAZY_DFA_CACHE entries (12 MiB per stream)
+    pub fn lazy_dfa(&mut self, cache_size: usize) -> &mut Self {
+        self.lazy_dfa_cache = cache_size.min(Self::MAX_LAZY_DFA_CACHE);
+        self
+    }
+
+    /// When enabled, the transitions are stored in a double array (base and check arrays) instead of dense and sparse rows : the rows of
+    /// all the states are interleaved into a single table, so finding a transition is always a single lookup, while the table stays compact.
+    /// This is best suited for large and mostly static dictionaries, especially with many distinct bytes (e.g. UTF-8 encoded Japanese).
+    /// The replacement results are exactly the same with both backends. Disabled by default
+    pub fn double_array(&mut self, yes: bool) -> &mut Self {
+        self.double_array = yes;
+        self
+    }
+
+    /// When enabled, states having identical futures are merged after the links are calculated, keeping the exact same replacement results.
+    /// Dictionaries sharing many endings (word inflections, URL paths, ...) then need far fewer states, at the cost of a longer construction.
+    /// The amount of merged states is reported by AhoCorasick::states_removed. Disabled by default
+    pub fn minimize(&mut self, yes: bool) -> &mut Self {
+        self.minimize = yes;
+        self
+    }
+
+    /// Builds the automaton. Same arguments as AhoCorasick::new
+    pub fn build(&self, replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> AhoCorasick {
+        self.build_from_iter(replacements)
+    }
+
+    /// Builds the automaton from words consumed one by one, e.g. read lazily from a dictionary file, so that the whole list of words never
+    /// needs to be in memory. The trie is compiled in place into the automaton, so the peak memory of the build stays close to the size
+    /// of the automaton (see AutomatonStats::heap_bytes). With minimize, the whole automaton is built before being minimized.
+    /// Words sorted in byte order (as in most dictionary files) are added faster, and their replacements are not copied again.
+    /// The result is the same in any order, except that for duplicated words, the last replacement wins
+    pub fn build_from_iter<I>(&self, replacements: I) -> AhoCorasick
+    where
+        I: IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>
+    {
+        AhoCorasick {
+            automaton: Arc::new(AcAutomaton::from_trie(Self::trie(replacements), self)),
+        }
+    }
+
+    pub(crate) fn trie<I>(replacements: I) -> Trie
+    where
+        I: IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>
+    {
+        let mut insertion = TrieInsertion::new();
+        for (word, replacement) in replacements.into_iter() {
+            insertion.insert(word, replacement);
+        }
+        let mut trie = insertion.trie;
+        trie.breadth_first_walk();
+        trie
+    }
+}
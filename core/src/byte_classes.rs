@@ -0,0 +1,97 @@
+use alloc::vec::Vec;
+use crate::trie::Trie;
+
+/// Partition of the 256 byte values into classes of bytes behaving the same way in every state of the automaton
+/// The transition tables are indexed by class instead of by byte, so their rows only need one entry per class
+#[derive(Debug, Clone)]
+pub(crate) struct ByteClasses {
+    classes: [u8; 256],
+    alphabet_len: usize,
+}
+
+impl ByteClasses {
+    /// Every byte is its own class, which is the same as not using classes at all
+    pub(crate) fn singletons() -> Self {
+        let mut classes = [0u8; 256];
+        for (byte, class) in classes.iter_mut().enumerate() {
+            *class = byte as u8;
+        }
+        Self {
+            classes,
+            alphabet_len: 256,
+        }
+    }
+
+    /// Computes the classes of the trie : two bytes are in the same class only if every node has the same child for both of them
+    /// Starting with all the bytes in a single class, each node splits the classes according to its children
+    pub(crate) fn from_trie(trie: &Trie) -> Self {
+        let mut byte_classes = Self {
+            classes: [0u8; 256],
+            alphabet_len: 1,
+        };
+        let mut class_sizes = [0usize; 256];
+        class_sizes[0] = 256;
+        let mut row: Vec<(u8, u32, u8)> = Vec::new();
+        for node in 0..trie.len() as u32 {
+            // Grouping the children by (current class, target) : each group smaller than its class gets split into a new class
+            row.clear();
+            row.extend(trie.children(node).map(|(val, child)| (byte_classes.classes[val as usize], child, val)));
+            row.sort_unstable();
+            for group in row.chunk_by(|a, b| (a.0, a.1) == (b.0, b.1)) {
+                let class = group[0].0 as usize;
+                if group.len() == class_sizes[class] {
+                    continue;
+                }
+                let new_class = byte_classes.alphabet_len;
+                for (_, _, val) in group {
+                    byte_classes.classes[*val as usize] = new_class as u8;
+                }
+                class_sizes[class] -= group.len();
+                class_sizes[new_class] = group.len();
+                byte_classes.alphabet_len += 1;
+            }
+        }
+        // Renumbering the classes in the order of their smallest byte, so that the numbering does not depend on the order of the nodes
+        let mut renumbered: [Option<u8>; 256] = [None; 256];
+        let mut next_class = 0;
+        for class in byte_classes.classes.iter_mut() {
+            *class = *renumbered[*class as usize].get_or_insert_with(|| {
+                next_class += 1;
+                (next_class - 1) as u8
+            });
+        }
+        byte_classes
+    }
+
+    /// Classes read back from a serialized automaton. Returns None if the table is not consistent with the amount of classes
+    pub(crate) fn from_raw(classes: [u8; 256], alphabet_len: usize) -> Option<Self> {
+        if alphabet_len == 0 || alphabet_len > 256 || classes.iter().any(|class| *class as usize >= alphabet_len) {
+            return None;
+        }
+        Some(Self {
+            classes,
+            alphabet_len,
+        })
+    }
+
+    /// The raw 256-entry table, as serialized
+    pub(crate) fn as_raw(&self) -> &[u8; 256] {
+        &self.classes
+    }
+
+    #[inline(always)]
+    pub(crate) fn get(&self, byte: u8) -> u8 {
+        self.classes[byte as usize]
+    }
+
+    /// Amount of classes, i.e. the length of a dense transitions row
+    #[inline(always)]
+    pub(crate) fn alphabet_len(&self) -> usize {
+        self.alphabet_len
+    }
+
+    /// All the bytes belonging to the class
+    pub(crate) fn bytes(&self, class: u8) -> impl Iterator<Item = u8> + '_ {
+        (0..=255u8).filter(move |byte| self.classes[*byte as usize] == class)
+    }
+}
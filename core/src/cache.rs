@@ -0,0 +1,181 @@
+use std::{collections::HashMap, hash::{BuildHasher, BuildHasherDefault, DefaultHasher}, sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError}};
+use crate::{AhoCorasick, builder::AhoCorasickBuilder};
+
+/// Cache of compiled automatons, keyed by a fingerprint of their configuration and dictionary : building the same dictionary again
+/// gives back the automaton already built, sharing its memory. It holds at most its capacity of automatons, evicting the least recently
+/// used one. It can be shared by any number of threads, and concurrent builds of the same dictionary only build it once.
+/// The fingerprint is a 64-bit hash, only used within the process. Two dictionaries with the same fingerprint are told apart by
+/// comparing the dictionary of the automaton found with the one given, so a collision only costs a build
+#[derive(Debug)]
+pub struct AutomatonCache<S = BuildHasherDefault<DefaultHasher>> {
+    capacity: usize,
+    hasher: S,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    slots: HashMap<u64, Vec<Slot>>, // Several slots for a fingerprint only when different configurations or dictionaries collide
+    len: usize, // Amount of slots
+    tick: u64, // Incremented at each access
+}
+
+#[derive(Debug)]
+struct Slot {
+    builder: AhoCorasickBuilder,
+    automaton: Arc<OnceLock<AhoCorasick>>, // Empty while being built
+    last_used: u64,
+}
+
+impl AutomatonCache {
+    /// Capacity of the process-wide cache
+    pub const GLOBAL_CAPACITY: usize = 64;
+
+    /// Cache holding at most the given amount of automatons
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, BuildHasherDefault::default())
+    }
+
+    /// Process-wide cache, of GLOBAL_CAPACITY automatons, used by AhoCorasick::new_cached and AhoCorasickBuilder::build_cached
+    pub fn global() -> &'static AutomatonCache {
+        static GLOBAL: OnceLock<AutomatonCache> = OnceLock::new();
+        GLOBAL.get_or_init(|| AutomatonCache::new(Self::GLOBAL_CAPACITY))
+    }
+}
+
+impl<S: BuildHasher> AutomatonCache<S> {
+    /// Cache holding at most the given amount of automatons, fingerprinting the dictionaries with the given hasher
+    pub fn with_hasher(capacity: usize, hasher: S) -> Self {
+        Self {
+            capacity,
+            hasher,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Automaton built from the replacements with the given configuration, either found in the cache or built and added to it.
+    /// The dictionary is normalized first : the order of the words and the replaced duplicates (the last one wins) do not change the fingerprint.
+    /// An automaton found is only returned once its dictionary is checked to be the one given, which walks all of its words
+    pub fn get_or_build(&self, builder: &AhoCorasickBuilder, replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> AhoCorasick {
+        if self.capacity == 0 {
+            return builder.build(replacements);
+        }
+        let replacements = normalize(replacements);
+        let fingerprint = self.hasher.hash_one((builder, &replacements));
+        let candidates: Vec<Arc<OnceLock<AhoCorasick>>> = {
+            let mut entries = self.lock();
+            entries.tick += 1;
+            let tick = entries.tick;
+            let mut slots: Vec<&mut Slot> = entries.slots.get_mut(&fingerprint).into_iter().flatten()
+                .filter(|slot| slot.builder == *builder)
+                .collect();
+            if let [slot] = slots.as_mut_slice() {
+                slot.last_used = tick; // When there are several, only the one found is marked, once its dictionary is compared
+            }
+            let candidates: Vec<Arc<OnceLock<AhoCorasick>>> = slots.iter().map(|slot| Arc::clone(&slot.automaton)).collect();
+            if candidates.is_empty() {
+                vec![self.insert(&mut entries, fingerprint, builder, OnceLock::new())]
+            } else {
+                candidates
+            }
+        };
+        // Built outside of the lock, so that the other dictionaries remain available meanwhile. The concurrent callers wait for this build
+        let mut replacements = Some(replacements);
+        let collided = candidates.len() > 1;
+        for slot in candidates {
+            let automaton = slot.get_or_init(|| builder.build_from_iter(replacements.take().expect("Logic error : built at most once")));
+            let found = match &replacements {
+                None => true, // Built by this call
+                Some(replacements) => automaton.automaton.words().eq(replacements.iter().cloned()),
+            };
+            if found {
+                if collided {
+                    self.touch(fingerprint, &slot);
+                }
+                return automaton.clone();
+            }
+        }
+        // None of the automatons with this fingerprint has this dictionary
+        let automaton = builder.build_from_iter(replacements.expect("Logic error : a candidate built by this call is returned"));
+        let mut entries = self.lock();
+        entries.tick += 1;
+        self.insert(&mut entries, fingerprint, builder, OnceLock::from(automaton.clone()));
+        automaton
+    }
+
+    /// Amount of automatons in the cache
+    pub fn len(&self) -> usize {
+        self.lock().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all the automatons from the cache. The ones still in use are only dropped once their last clone is
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.slots.clear();
+        entries.len = 0;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Marks the slot holding the automaton as the most recently used one
+    fn touch(&self, fingerprint: u64, automaton: &Arc<OnceLock<AhoCorasick>>) {
+        let mut entries = self.lock();
+        entries.tick += 1;
+        let tick = entries.tick;
+        if let Some(slot) = entries.slots.get_mut(&fingerprint).into_iter().flatten().find(|slot| Arc::ptr_eq(&slot.automaton, automaton)) {
+            slot.last_used = tick;
+        }
+    }
+
+    /// Adds a slot, used at the current tick, evicting the least recently used one when the cache is full
+    fn insert(&self, entries: &mut Entries, fingerprint: u64, builder: &AhoCorasickBuilder, automaton: OnceLock<AhoCorasick>) -> Arc<OnceLock<AhoCorasick>> {
+        if entries.len >= self.capacity {
+            let oldest = entries.slots.iter()
+                .flat_map(|(fingerprint, slots)| slots.iter().enumerate().map(move |(index, slot)| (slot.last_used, *fingerprint, index)))
+                .min();
+            if let Some((_, oldest, index)) = oldest {
+                let slots = entries.slots.get_mut(&oldest).expect("Logic error : the oldest slot was just found");
+                slots.remove(index);
+                if slots.is_empty() {
+                    entries.slots.remove(&oldest);
+                }
+                entries.len -= 1;
+            }
+        }
+        let automaton = Arc::new(automaton);
+        entries.slots.entry(fingerprint).or_default().push(Slot {
+            builder: builder.clone(),
+            automaton: Arc::clone(&automaton),
+            last_used: entries.tick,
+        });
+        entries.len += 1;
+        automaton
+    }
+}
+
+impl AhoCorasickBuilder {
+    /// Same as build, through the process-wide AutomatonCache : the same dictionary with the same configuration is only built once
+    pub fn build_cached(&self, replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> AhoCorasick {
+        AutomatonCache::global().get_or_build(self, replacements)
+    }
+}
+
+/// Sorted by word, keeping the last replacement of the duplicated words
+fn normalize(mut replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    // The sort is stable : duplicates stay in their original order, and the last one replaces the previous ones
+    replacements.sort_by(|(left, _), (right, _)| left.cmp(right));
+    replacements.dedup_by(|next, previous| {
+        let duplicate = next.0 == previous.0;
+        if duplicate {
+            core::mem::swap(next, previous);
+        }
+        duplicate
+    });
+    replacements
+}
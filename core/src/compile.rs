@@ -0,0 +1,233 @@
+use core::ops::Range;
+use alloc::{boxed::Box, vec, vec::Vec};
+use crate::{
+    automaton::{AcAutomaton, Rows, State, StateId}, build_async::{BuildProgress, CompilePass}, builder::AhoCorasickBuilder,
+    byte_classes::ByteClasses, minimize::Minimization, prefilter::Prefilter, trie::Trie,
+};
+
+/// Compiles the trie (with its links already calculated) into the contiguous AcAutomaton, pass after pass, with at most a slice of
+/// states per step (a single refinement round per step while minimizing). Used by the blocking and the async builds
+///
+/// The trie is consumed : its arena is renumbered in breadth-first order (so that the shallow, most visited states are packed together)
+/// and becomes the state table in place, the transitions rows being written over the children lists as they are emitted. Its
+/// replacements become the replacement tables. Only the transitions pools are allocated, at their final size, so the peak memory of
+/// the build stays close to the size of the automaton. With minimize, the tables are then minimized in place, once the trie is dropped
+pub(crate) struct Compilation {
+    pass: Option<Pass>, // Only taken while a step moves to the next pass
+    options: AhoCorasickBuilder,
+}
+
+enum Pass {
+    // The trie is boxed, as its row of root makes it much larger than the other passes
+    Rows { trie: Box<Trie>, byte_classes: ByteClasses, rows: Rows, next: usize },
+    Minimize { draft: Draft, minimization: Minimization },
+    DoubleArray { draft: Draft, rows: Rows, next: usize },
+    Dfa { ac: AcAutomaton, dfa: Vec<StateId>, next: usize },
+    Done(AcAutomaton),
+}
+
+/// Tables of an automaton being compiled, which can still be modified in place. The rows are dense and sparse rows in state order,
+/// unless the double array was placed directly
+pub(crate) struct Draft {
+    pub(crate) byte_classes: ByteClasses,
+    pub(crate) states: Vec<State>,
+    pub(crate) dense: Vec<StateId>,
+    pub(crate) check: Vec<StateId>,
+    pub(crate) sparse_classes: Vec<u8>,
+    pub(crate) sparse_next: Vec<StateId>,
+    pub(crate) replacement_bounds: Vec<[u32; 2]>,
+    pub(crate) replacement_bytes: Vec<u8>,
+    pub(crate) states_removed: u32,
+}
+
+impl Compilation {
+    /// Renumbers the trie and computes its byte classes, in one go : the passes start from there
+    pub(crate) fn new(mut trie: Trie, options: &AhoCorasickBuilder) -> Self {
+        trie.renumber_breadth_first();
+        trie.compact_words();
+        let byte_classes = if options.byte_classes {
+            ByteClasses::from_trie(&trie)
+        } else {
+            ByteClasses::singletons()
+        };
+        // Minimizing rewrites the rows in place, which needs them in state order : the double array is then placed afterwards
+        let double_array = options.double_array && !options.minimize;
+        let rows = Rows::new(double_array, byte_classes.alphabet_len(), (0..trie.len()).map(|node| trie.children(node as u32).count()));
+        Self {
+            pass: Some(Pass::Rows { trie: Box::new(trie), byte_classes, rows, next: 0 }),
+            options: options.clone(),
+        }
+    }
+
+    /// Runs the next slice of work, moving to the next pass once the current one is complete. Returns the progress made, or None
+    /// once the automaton is complete
+    pub(crate) fn step(&mut self, slice: usize) -> Option<BuildProgress> {
+        let pass = self.pass.take().expect("Logic error : the pass is always put back");
+        let (pass, progress) = self.advance(pass, slice);
+        self.pass = Some(pass);
+        progress
+    }
+
+    /// The compiled automaton. Runs the remaining passes at once, if any
+    pub(crate) fn finish(mut self) -> AcAutomaton {
+        while self.step(usize::MAX).is_some() {}
+        match self.pass {
+            Some(Pass::Done(ac)) => ac,
+            _ => unreachable!("Logic error : the passes are all done"),
+        }
+    }
+
+    fn advance(&self, pass: Pass, slice: usize) -> (Pass, Option<BuildProgress>) {
+        match pass {
+            Pass::Rows { mut trie, byte_classes, mut rows, next } => {
+                let end = next.saturating_add(slice).min(trie.len());
+                let mut row: Vec<(u8, StateId)> = Vec::new();
+                for node in next as u32..end as u32 {
+                    // Within a row, each child is reached through a different class
+                    row.clear();
+                    row.extend(trie.children(node).map(|(val, child)| (byte_classes.get(val), StateId(child))));
+                    row.sort_unstable();
+                    // The children come after their parent in breadth-first order : the lists still to be walked are not overwritten
+                    let (row_start, row_len) = rows.push(StateId(node), &row);
+                    let this = trie.node_mut(node);
+                    this.row_start = row_start;
+                    this.row_len = row_len;
+                }
+                let progress = Self::progress(CompilePass::Rows, end, trie.len());
+                if end < trie.len() {
+                    return (Pass::Rows { trie, byte_classes, rows, next: end }, progress);
+                }
+                let (mut states, replacement_bounds, replacement_bytes) = (*trie).into_tables();
+                states[StateId::ROOT.index()].suffix_link = StateId::ROOT;
+                let (dense, check, sparse_classes, sparse_next) = rows.finish();
+                let draft = Draft {
+                    byte_classes,
+                    states,
+                    dense,
+                    check,
+                    sparse_classes,
+                    sparse_next,
+                    replacement_bounds,
+                    replacement_bytes,
+                    states_removed: 0,
+                };
+                if self.options.minimize {
+                    (Pass::Minimize { minimization: Minimization::new(&draft), draft }, progress)
+                } else {
+                    (self.freeze(draft), progress)
+                }
+            },
+            Pass::Minimize { mut draft, mut minimization } => {
+                let stable = !minimization.refine(&draft);
+                let progress = Self::progress(CompilePass::Minimize, minimization.class_count(), draft.states.len());
+                if !stable {
+                    return (Pass::Minimize { draft, minimization }, progress);
+                }
+                minimization.merge(&mut draft);
+                if self.options.double_array {
+                    let rows = Rows::new(true, draft.byte_classes.alphabet_len(), core::iter::empty());
+                    (Pass::DoubleArray { draft, rows, next: 0 }, progress)
+                } else {
+                    (self.freeze(draft), progress)
+                }
+            },
+            Pass::DoubleArray { mut draft, mut rows, next } => {
+                // The dense and sparse rows are read one at a time, but the pools are only freed once all of them are placed
+                let end = next.saturating_add(slice).min(draft.states.len());
+                let mut row: Vec<(u8, StateId)> = Vec::new();
+                for state in next as u32..end as u32 {
+                    row.clear();
+                    row.extend(draft.row(state));
+                    let (row_start, row_len) = rows.push(StateId(state), &row);
+                    let this = &mut draft.states[state as usize];
+                    this.row_start = row_start;
+                    this.row_len = row_len;
+                }
+                let progress = Self::progress(CompilePass::DoubleArray, end, draft.states.len());
+                if end < draft.states.len() {
+                    return (Pass::DoubleArray { draft, rows, next: end }, progress);
+                }
+                (draft.dense, draft.check, draft.sparse_classes, draft.sparse_next) = rows.finish();
+                (self.freeze(draft), progress)
+            },
+            Pass::Dfa { mut ac, mut dfa, next } => {
+                let end = next.saturating_add(slice).min(ac.states.len());
+                ac.fill_dfa(&mut dfa, next..end);
+                let progress = Self::progress(CompilePass::Dfa, end, ac.states.len());
+                if end < ac.states.len() {
+                    return (Pass::Dfa { ac, dfa, next: end }, progress);
+                }
+                ac.dfa = dfa.into();
+                ac.init_prefilter();
+                (Pass::Done(ac), progress)
+            },
+            Pass::Done(ac) => (Pass::Done(ac), None),
+        }
+    }
+
+    /// Makes the tables read-only. The DFA, if enabled, still has to be filled
+    fn freeze(&self, draft: Draft) -> Pass {
+        let mut ac = AcAutomaton {
+            states: draft.states.into(),
+            byte_classes: draft.byte_classes,
+            dense: draft.dense.into(),
+            check: draft.check.into(),
+            sparse_classes: draft.sparse_classes.into(),
+            sparse_next: draft.sparse_next.into(),
+            replacement_bounds: draft.replacement_bounds.into(),
+            replacement_bytes: draft.replacement_bytes.into(),
+            dfa: Vec::new().into(),
+            states_removed: draft.states_removed,
+            // Useless with the full DFA, which already resolves every transition
+            lazy_dfa_cache: if self.options.dfa { 0 } else { self.options.lazy_dfa_cache as u32 },
+            flags: if self.options.prefilter { 0 } else { AcAutomaton::FLAG_NO_PREFILTER },
+            prefilter: Prefilter::Disabled,
+        };
+        if self.options.dfa {
+            let dfa = vec![StateId::ROOT; ac.states.len() * ac.byte_classes.alphabet_len()];
+            return Pass::Dfa { ac, dfa, next: 0 };
+        }
+        ac.init_prefilter();
+        Pass::Done(ac)
+    }
+
+    fn progress(pass: CompilePass, states: usize, total: usize) -> Option<BuildProgress> {
+        Some(BuildProgress::Compiling { pass, states, total })
+    }
+}
+
+impl Draft {
+    /// Direct transitions of the state, sorted by class. Only for dense and sparse rows
+    pub(crate) fn row(&self, state: u32) -> impl Iterator<Item = (u8, StateId)> + '_ {
+        let this = &self.states[state as usize];
+        let row_start = this.row_start as usize;
+        let dense = (this.row_len == AcAutomaton::DENSE_ROW).then(|| {
+            self.dense[row_start..row_start + self.byte_classes.alphabet_len()].iter().enumerate()
+                .filter(|(_, next)| **next != StateId::FAIL)
+                .map(|(class, next)| (class as u8, *next))
+        });
+        let sparse = (this.row_len != AcAutomaton::DENSE_ROW).then(|| {
+            let row_end = row_start + this.row_len as usize;
+            self.sparse_classes[row_start..row_end].iter().copied().zip(self.sparse_next[row_start..row_end].iter().copied())
+        });
+        dense.into_iter().flatten().chain(sparse.into_iter().flatten())
+    }
+}
+
+impl AcAutomaton {
+    /// Resolves the transition of every (state, byte class) pair of the given states. States are in breadth-first order, so the row
+    /// of a suffix link is always complete before being used : a missing transition simply copies the one of the suffix link
+    fn fill_dfa(&self, dfa: &mut [StateId], states: Range<usize>) {
+        let alphabet_len = self.byte_classes.alphabet_len();
+        for state in states {
+            let suffix_link = self.states[state].suffix_link.index();
+            for class in 0..alphabet_len {
+                dfa[state * alphabet_len + class] = match self.goto(StateId(state as u32), class as u8) {
+                    Some(next) => next,
+                    None if state == StateId::ROOT.index() => StateId::ROOT,
+                    None => dfa[suffix_link * alphabet_len + class]
+                };
+            }
+        }
+    }
+}
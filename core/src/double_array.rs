@@ -0,0 +1,78 @@
+use alloc::vec::Vec;
+use crate::{automaton::StateId, table::reserve_compact};
+
+/// Placement of the transition rows into a double array : all the rows are interleaved into the single dense pool, each row
+/// starting at a base chosen so that its transitions only land on free entries. The check table tells which state owns each entry,
+/// so a transition is found with a single lookup, while the holes of a row are filled by the transitions of the other rows
+#[derive(Debug, Default)]
+pub(crate) struct DoubleArray {
+    pub(crate) next: Vec<StateId>, // Transition target of each entry. StateId::FAIL for free entries
+    pub(crate) check: Vec<StateId>, // Owner of each entry. StateId::FAIL for free entries
+    first_free: usize, // All the entries before it are used
+}
+
+impl DoubleArray {
+    /// Reserves a whole dense row for the state, e.g. for root which is the most visited state. Returns the start of the row
+    pub(crate) fn push_dense(&mut self, owner: StateId, row: &[(u8, StateId)], alphabet_len: usize) -> usize {
+        let base = self.next.len();
+        self.grow(base + alphabet_len);
+        self.next.resize(base + alphabet_len, StateId::FAIL);
+        self.check.resize(base + alphabet_len, owner);
+        for (class, next) in row.iter() {
+            self.next[base + *class as usize] = *next;
+        }
+        self.advance_first_free();
+        base
+    }
+
+    /// Places the transitions (sorted by class) of the state at the first base where they all land on free entries. Returns the base
+    /// The tables are always long enough to read alphabet_len entries from any returned base, so lookups never need a bounds check
+    pub(crate) fn place(&mut self, owner: StateId, row: &[(u8, StateId)], alphabet_len: usize) -> usize {
+        let base = match row.first() {
+            None => 0, // No transition : any base works, as no entry can be owned by the state
+            Some((first_class, _)) => {
+                let first_class = *first_class as usize;
+                let mut position = self.first_free.max(first_class);
+                loop {
+                    let base = position - first_class;
+                    if row.iter().all(|(class, _)| self.is_free(base + *class as usize)) {
+                        break base;
+                    }
+                    position += 1;
+                    while !self.is_free(position) {
+                        position += 1;
+                    }
+                }
+            }
+        };
+        if self.next.len() < base + alphabet_len {
+            self.grow(base + alphabet_len);
+            self.next.resize(base + alphabet_len, StateId::FAIL);
+            self.check.resize(base + alphabet_len, StateId::FAIL);
+        }
+        for (class, next) in row.iter() {
+            self.next[base + *class as usize] = *next;
+            self.check[base + *class as usize] = owner;
+        }
+        self.advance_first_free();
+        base
+    }
+
+    /// Makes room for the given length, growing the tables by a quarter at a time (see reserve_compact)
+    fn grow(&mut self, len: usize) {
+        let additional = len.saturating_sub(self.next.len());
+        reserve_compact(&mut self.next, additional);
+        reserve_compact(&mut self.check, additional);
+    }
+
+    #[inline]
+    fn is_free(&self, position: usize) -> bool {
+        self.check.get(position).is_none_or(|owner| *owner == StateId::FAIL)
+    }
+
+    fn advance_first_free(&mut self) {
+        while !self.is_free(self.first_free) {
+            self.first_free += 1;
+        }
+    }
+}
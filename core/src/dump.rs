@@ -0,0 +1,85 @@
+use core::fmt::{Display, Write};
+use alloc::{format, string::String};
+use crate::automaton::{AcAutomaton, StateId};
+
+/// One line per state, in the order of the StateIds (breadth-first) : its depth, links, word, and transitions sorted by byte.
+/// The output only depends on the dictionary and the configuration, so dumps of two builds can be compared
+impl Display for AcAutomaton {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for index in 0..self.states.len() {
+            let id = StateId(index as u32);
+            let state = &self.states[index];
+            if index > 0 {
+                f.write_char('\n')?;
+            }
+            write!(f, "{}: depth {}, suffix {}", id.0, state.depth, state.suffix_link.0)?;
+            if state.output_link != StateId::FAIL {
+                write!(f, ", output {}", state.output_link.0)?;
+            }
+            if self.is_word(id) {
+                match self.replacement(id) {
+                    Some(replacement) => write!(f, ", word => \"{}\"", replacement.escape_ascii())?,
+                    None => write!(f, ", protected word")?,
+                }
+            }
+            f.write_str(" [")?;
+            for (position, (val, next)) in self.transitions(id).into_iter().enumerate() {
+                if position > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{} => {}", core::ascii::escape_default(val), next.0)?;
+            }
+            f.write_char(']')?;
+        }
+        Ok(())
+    }
+}
+
+impl AcAutomaton {
+    /// Graphviz DOT description of the automaton, e.g. to be rendered with the dot command of Graphviz. Trie transitions are solid edges labelled with
+    /// their byte, suffix links are dashed blue edges (the ones to root are left out, as they would hide everything else), and output links
+    /// are dotted red edges. Word states are double circles, labelled with their replacement. Non-printable bytes are escaped as in Rust
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph automaton {\n    rankdir=LR;\n    node [shape=circle];\n");
+        for index in 0..self.states.len() {
+            let id = StateId(index as u32);
+            if self.is_word(id) {
+                let label = match self.replacement(id) {
+                    Some(replacement) => format!("{}\n=> \"{}\"", index, replacement.escape_ascii()),
+                    None => format!("{}\nprotected", index),
+                };
+                dot.push_str(&format!("    {} [shape=doublecircle, label=\"{}\"];\n", index, dot_escape(&label)));
+            }
+        }
+        for index in 0..self.states.len() {
+            let id = StateId(index as u32);
+            for (val, next) in self.transitions(id) {
+                let label = format!("{}", core::ascii::escape_default(val));
+                dot.push_str(&format!("    {} -> {} [label=\"{}\"];\n", index, next.0, dot_escape(&label)));
+            }
+            let state = &self.states[index];
+            if state.suffix_link != StateId::ROOT {
+                dot.push_str(&format!("    {} -> {} [style=dashed, color=blue];\n", index, state.suffix_link.0));
+            }
+            if state.output_link != StateId::FAIL {
+                dot.push_str(&format!("    {} -> {} [style=dotted, color=red];\n", index, state.output_link.0));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Escapes a label for a DOT string : quotes and backslashes are escaped, and line breaks become DOT line breaks
+fn dot_escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for char in label.chars() {
+        match char {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
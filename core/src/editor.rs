@@ -0,0 +1,259 @@
+use std::{sync::{Arc, PoisonError, RwLock}};
+use futures::{AsyncRead, AsyncWrite};
+use crate::{AhoCorasick, AhoCorasickAsyncReader, AhoCorasickAsyncWriter, automaton::AcAutomaton, builder::AhoCorasickBuilder, trie::Trie};
+
+/// Editable dictionary : words can be inserted and removed after the automaton is built.
+/// The build-time trie is kept along with the links needed to patch it : each edit only recomputes the suffix and output links
+/// of the nodes it actually affects. The edits become visible once published : the trie is then compiled into a new snapshot,
+/// which replaces the previous one in the shared AhoCorasickHandle. Streams already running keep the snapshot they started with
+#[derive(Debug)]
+pub struct AhoCorasickEditor {
+    trie: Trie,
+    parents: Vec<(u32, u8)>, // Parent of each node, and the byte leading from the parent to the node. Unused for root and free nodes
+    suffix_children: Vec<Vec<u32>>, // Nodes whose suffix link points to each node, in no particular order
+    free: Vec<u32>, // Nodes removed from the trie, reused by the next insertions
+    options: AhoCorasickBuilder,
+    pending: bool, // True if there are edits which have not been published yet
+    handle: AhoCorasickHandle,
+}
+
+/// Shared access to the latest published snapshot of an AhoCorasickEditor. Cloning it is cheap, and it can be sent to other threads
+#[derive(Debug, Clone)]
+pub struct AhoCorasickHandle {
+    published: Arc<RwLock<AhoCorasick>>,
+}
+
+impl AhoCorasickEditor {
+    /// Builds the editor and publishes the initial snapshot, with the default configuration. Same arguments as AhoCorasick::new
+    pub fn new(replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Self {
+        AhoCorasickBuilder::new().build_editor(replacements)
+    }
+
+    pub(crate) fn from_trie(trie: Trie, options: AhoCorasickBuilder) -> Self {
+        let mut parents = vec![(Trie::ROOT, 0u8); trie.len()];
+        let mut suffix_children = vec![Vec::new(); trie.len()];
+        for node in 0..trie.len() as u32 {
+            for (val, child) in trie.children(node) {
+                parents[child as usize] = (node, val);
+            }
+            if let Some(suffix_link) = trie.suffix_link(node) {
+                suffix_children[suffix_link as usize].push(node);
+            }
+        }
+        let snapshot = AhoCorasick {
+            automaton: Arc::new(AcAutomaton::from_trie(trie.clone(), &options)),
+        };
+        Self {
+            trie,
+            parents,
+            suffix_children,
+            free: Vec::new(),
+            options,
+            pending: false,
+            handle: AhoCorasickHandle {
+                published: Arc::new(RwLock::new(snapshot)),
+            },
+        }
+    }
+
+    /// Adds a word with its optional replacement. If the word was already present, its replacement is overwritten
+    pub fn insert(&mut self, word: Vec<u8>, replacement: Option<Vec<u8>>) {
+        // Walking down the existing nodes, then appending the missing ones
+        let mut node = Trie::ROOT;
+        let mut new_nodes: Vec<u32> = Vec::new();
+        for val in word.iter() {
+            node = match self.trie.child(node, *val) {
+                Some(child) => child,
+                None => {
+                    let child = self.new_node(node, *val);
+                    new_nodes.push(child);
+                    child
+                }
+            };
+        }
+        let was_word = self.trie.set_word(node, replacement);
+        self.pending = true;
+
+        // New nodes are in increasing depth, so the suffix links of all the shallower nodes are already up to date when linking one
+        for new_node in new_nodes {
+            let (parent, val) = self.parents[new_node as usize];
+            let suffix_link = self.trie.child_suffix_link(parent, val);
+            self.link(new_node, suffix_link);
+            self.adopt_suffix_children(new_node, suffix_link, &word);
+        }
+        if !was_word {
+            self.refresh_output_links(node);
+        }
+    }
+
+    /// Removes a word. Returns false if it was not in the dictionary
+    pub fn remove(&mut self, word: &[u8]) -> bool {
+        let mut node = Trie::ROOT;
+        for val in word.iter() {
+            match self.trie.child(node, *val) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        if !self.trie.unset_word(node) {
+            return false;
+        }
+        self.pending = true;
+        self.refresh_output_links(node);
+
+        // Pruning the nodes which do not lead to any word anymore, from the leaf up
+        while node != Trie::ROOT && !self.trie.is_word(node) && !self.trie.has_children(node) {
+            let (parent, val) = self.parents[node as usize];
+            self.trie.remove_child(parent, val);
+            self.delete_node(node);
+            node = parent;
+        }
+        true
+    }
+
+    /// Returns true if some edits have not been published yet
+    pub fn has_pending_edits(&self) -> bool {
+        self.pending
+    }
+
+    /// Compiles the edited dictionary into a new snapshot, and makes it the one returned by the handle.
+    /// Streams created from the previous snapshot keep using it until they are dropped. Does nothing if there are no pending edits
+    pub fn publish(&mut self) -> AhoCorasick {
+        if self.pending {
+            // Dropping the replacements overwritten or removed, so that they do not pile up across the edits
+            self.trie.compact_words();
+            let snapshot = AhoCorasick {
+                automaton: Arc::new(AcAutomaton::from_trie(self.trie.clone(), &self.options)),
+            };
+            *self.handle.published.write().unwrap_or_else(PoisonError::into_inner) = snapshot;
+            self.pending = false;
+        }
+        self.handle.snapshot()
+    }
+
+    /// Latest published snapshot
+    pub fn snapshot(&self) -> AhoCorasick {
+        self.handle.snapshot()
+    }
+
+    /// Handle giving access to the published snapshots, e.g. from the tasks creating the streams
+    pub fn handle(&self) -> AhoCorasickHandle {
+        self.handle.clone()
+    }
+
+    /// Appends a new node below the parent, reusing a removed one if possible. Its links are set separately
+    fn new_node(&mut self, parent: u32, val: u8) -> u32 {
+        let depth = self.trie.depth(parent) + 1;
+        let node = match self.free.pop() {
+            Some(node) => {
+                self.trie.reset_node(node, depth);
+                node
+            },
+            None => {
+                self.parents.push((parent, val));
+                self.suffix_children.push(Vec::new());
+                self.trie.push_node(depth)
+            }
+        };
+        self.parents[node as usize] = (parent, val);
+        self.trie.add_child(parent, val, node);
+        node
+    }
+
+    /// Removes a leaf node which is not a word : the nodes whose suffix link pointed to it now point to its own suffix link,
+    /// which is their next longest suffix. Their output links do not change, as the removed node was not a word
+    fn delete_node(&mut self, node: u32) {
+        let suffix_link = self.trie.suffix_link(node).expect("Logic error : only root has no suffix link");
+        let orphans = std::mem::take(&mut self.suffix_children[node as usize]);
+        for orphan in orphans.iter() {
+            self.trie.set_suffix_link(*orphan, suffix_link);
+        }
+        let siblings = &mut self.suffix_children[suffix_link as usize];
+        if let Some(position) = siblings.iter().position(|sibling| *sibling == node) {
+            siblings.swap_remove(position);
+        }
+        siblings.extend(orphans);
+        self.trie.reset_node(node, 0);
+        self.free.push(node);
+    }
+
+    /// Points the suffix link of the node to the given node, and updates its output link accordingly
+    fn link(&mut self, node: u32, suffix_link: u32) {
+        if let Some(previous) = self.trie.suffix_link(node) {
+            let siblings = &mut self.suffix_children[previous as usize];
+            if let Some(position) = siblings.iter().position(|sibling| *sibling == node) {
+                siblings.swap_remove(position);
+            }
+        }
+        let output_link = self.trie.output_link_through(suffix_link);
+        self.trie.set_suffix_link(node, suffix_link);
+        self.trie.set_output_link(node, output_link);
+        self.suffix_children[suffix_link as usize].push(node);
+    }
+
+    /// After inserting a node, the only nodes whose suffix link changes are the deeper nodes ending with the new node's bytes :
+    /// their longest suffix was the new node's suffix link before the insertion, so they are all among its suffix children
+    fn adopt_suffix_children(&mut self, new_node: u32, suffix_link: u32, word: &[u8]) {
+        let depth = self.trie.depth(new_node) as usize;
+        let suffix = &word[..depth];
+        let adopted: Vec<u32> = self.suffix_children[suffix_link as usize].iter()
+            .copied()
+            .filter(|candidate| *candidate != new_node && self.trie.depth(*candidate) as usize > depth && self.ends_with(*candidate, suffix))
+            .collect();
+        for node in adopted {
+            self.link(node, new_node);
+        }
+    }
+
+    /// Returns true if the bytes leading to the node end with the given bytes
+    fn ends_with(&self, mut node: u32, suffix: &[u8]) -> bool {
+        for expected in suffix.iter().rev() {
+            let (parent, val) = self.parents[node as usize];
+            if val != *expected {
+                return false;
+            }
+            node = parent;
+        }
+        true
+    }
+
+    /// Recomputes the output links depending on the node, after it became a word or stopped being one.
+    /// Those are the nodes reaching it through suffix links, without any other word in between
+    fn refresh_output_links(&mut self, node: u32) {
+        let mut to_walk = vec![node];
+        while let Some(node) = to_walk.pop() {
+            let output_link = self.trie.output_link_through(node);
+            for position in 0..self.suffix_children[node as usize].len() {
+                let child = self.suffix_children[node as usize][position];
+                self.trie.set_output_link(child, output_link);
+                if !self.trie.is_word(child) {
+                    to_walk.push(child);
+                }
+            }
+        }
+    }
+}
+
+impl AhoCorasickBuilder {
+    /// Builds an editor, allowing to insert and remove words afterwards. Every published snapshot is compiled with this configuration
+    pub fn build_editor(&self, replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> AhoCorasickEditor {
+        AhoCorasickEditor::from_trie(Self::trie(replacements), self.clone())
+    }
+}
+
+impl AhoCorasickHandle {
+    /// Latest published snapshot. It is not affected by the later edits, so a stream created from it keeps a consistent dictionary
+    pub fn snapshot(&self) -> AhoCorasick {
+        self.published.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Obtain a reader performing the replacements of the latest published snapshot
+    pub fn reader<R: AsyncRead>(&self, source: R) -> AhoCorasickAsyncReader<R> {
+        self.snapshot().into_reader(source)
+    }
+
+    /// Obtain a writer performing the replacements of the latest published snapshot
+    pub fn writer<W: AsyncWrite>(&self, sink: W) -> AhoCorasickAsyncWriter<W> {
+        self.snapshot().into_writer(sink)
+    }
+}
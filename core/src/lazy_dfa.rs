@@ -0,0 +1,77 @@
+use alloc::{boxed::Box, vec};
+use crate::automaton::{AcAutomaton, StateId};
+
+/// Bounded cache of resolved transitions, filled on demand as a stream is processed : once a (state, byte) transition has been resolved
+/// by following suffix links, the next occurrences are a single lookup, as with a full DFA, without resolving the transitions never used.
+/// It is owned by a single cursor, so it needs no synchronization. The cache is direct-mapped : each transition can only be cached in one
+/// slot, and evicts the transition previously cached in it, so the memory used never grows past the configured amount of entries
+#[derive(Debug, Clone)]
+pub(crate) struct LazyDfaCache {
+    entries: Box<[CacheEntry]>,
+    shift: u32, // Keeps the highest bits of the hash : as many as needed to index the entries
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CacheEntry {
+    state: StateId, // StateId::FAIL when the entry is empty
+    byte: u8,
+    next: StateId,
+}
+
+/// Hit and miss counters of the lazy DFA cache of a stream, to tune its size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LazyDfaStats {
+    /// Transitions found in the cache
+    pub hits: u64,
+    /// Transitions resolved by following suffix links, and then cached
+    pub misses: u64,
+}
+
+impl LazyDfaCache {
+    const EMPTY: CacheEntry = CacheEntry {
+        state: StateId::FAIL,
+        byte: 0,
+        next: StateId::FAIL,
+    };
+
+    /// Cache holding up to the given amount of transitions, rounded up to a power of two
+    pub(crate) fn new(size: usize) -> Self {
+        let size = size.max(1).next_power_of_two();
+        Self {
+            entries: vec![Self::EMPTY; size].into_boxed_slice(),
+            shift: u64::BITS - size.trailing_zeros(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Finds the next state, from the cache if the transition was already resolved
+    #[inline(always)]
+    pub(crate) fn next_state(&mut self, automaton: &AcAutomaton, state: StateId, byte: u8) -> StateId {
+        // Fibonacci hashing : the multiplication spreads the key over the highest bits, which are kept as the index
+        let key = ((state.0 as u64) << 8) | byte as u64;
+        let index = key.wrapping_mul(0x9E37_79B9_7F4A_7C15).checked_shr(self.shift).unwrap_or(0) as usize;
+        let entry = &mut self.entries[index];
+        if entry.state == state && entry.byte == byte {
+            self.hits += 1;
+            return entry.next;
+        }
+        self.misses += 1;
+        let next = automaton.next_state(state, byte);
+        *entry = CacheEntry {
+            state,
+            byte,
+            next,
+        };
+        next
+    }
+
+    pub(crate) fn stats(&self) -> LazyDfaStats {
+        LazyDfaStats {
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+}
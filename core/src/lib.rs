@@ -0,0 +1,199 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+use alloc::{sync::Arc, vec::Vec};
+pub use automaton::{AcAutomaton, StateId};
+pub use build_async::{BuildCancelled, BuildProgress, CancellationToken, CompilePass};
+pub use builder::AhoCorasickBuilder;
+#[cfg(feature = "std")]
+pub use cache::AutomatonCache;
+#[cfg(feature = "std")]
+pub use editor::{AhoCorasickEditor, AhoCorasickHandle};
+pub use lazy_dfa::LazyDfaStats;
+pub use merge::{ConflictPolicy, MergeConflict};
+pub use replacer::{Replacer, ReplacerOutput};
+pub use serialize::DeserializeError;
+pub use stats::AutomatonStats;
+#[cfg(feature = "std")]
+pub use stream::AhoCorasickStream;
+#[cfg(feature = "std")]
+pub use token::TokenAhoCorasick;
+pub use trace::{Trace, TraceOutcome, TraceStep};
+#[cfg(feature = "std")]
+use futures::{AsyncRead, AsyncWrite, AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "std")]
+pub use reader::AhoCorasickAsyncReader;
+#[cfg(feature = "std")]
+pub use writer::AhoCorasickAsyncWriter;
+
+mod automaton;
+mod build_async;
+mod builder;
+mod byte_classes;
+mod compile;
+#[cfg(feature = "std")]
+mod cache;
+mod double_array;
+mod dump;
+#[cfg(feature = "std")]
+mod editor;
+mod lazy_dfa;
+mod merge;
+mod minimize;
+mod prefilter;
+#[cfg(feature = "std")]
+mod reader;
+mod replacer;
+mod serialize;
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
+mod table;
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+mod teddy;
+#[cfg(feature = "std")]
+mod token;
+mod trace;
+mod trie;
+#[cfg(feature = "std")]
+mod writer;
+
+/// Compiled automaton, shared behind an Arc. It is Send + Sync, so a single instance can serve any number of streams across threads
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    pub automaton: Arc<AcAutomaton>,
+}
+
+impl AhoCorasick {
+    /// Instantiation of the automaton. The automaton itself is immutable : to add or remove replacements after instantiation, use AhoCorasickEditor
+    /// The constructor argument is a tuple with the searched word as the first element, and an optional replacement as second
+    /// Currently the only purpose is performing replacements, so there is little point in having None.
+    /// Note that even if None is set, after the word is matched, the state is reset back to root
+    pub fn new(replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Self {
+        AhoCorasickBuilder::new().build(replacements)
+    }
+
+    /// Same as new, through the process-wide AutomatonCache : when the same dictionary (in any order) was already built, the automaton
+    /// is shared instead of being built again
+    #[cfg(feature = "std")]
+    pub fn new_cached(replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Self {
+        AutomatonCache::global().get_or_build(&AhoCorasickBuilder::new(), replacements)
+    }
+
+    /// Same as new, without blocking the executor : the automaton is built in slices, yielding to the executor between them.
+    /// The progress callback is called after each slice, and cancelling the token stops the build. See AhoCorasickBuilder::build_async
+    pub async fn new_async<P: FnMut(BuildProgress)>(
+        replacements: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+        progress: P,
+        cancellation: CancellationToken
+    ) -> Result<Self, BuildCancelled> {
+        AhoCorasickBuilder::new().build_async(replacements, progress, cancellation).await
+    }
+
+    /// Builder allowing to customize the automaton, e.g. AhoCorasick::builder().dfa(true).build(replacements)
+    pub fn builder() -> AhoCorasickBuilder {
+        AhoCorasickBuilder::new()
+    }
+
+    /// Builds one automaton with the words of both, e.g. to apply the union of two dictionaries. The words are read back from the compiled
+    /// automatons, so the original lists are not needed. When a word has different replacements in both, the conflict policy decides
+    /// whether the merge fails, or which replacement is kept. Built with the default configuration, see AhoCorasickBuilder::merge to change it
+    pub fn merge(left: &AhoCorasick, right: &AhoCorasick, conflict_policy: ConflictPolicy) -> Result<Self, MergeConflict> {
+        AhoCorasickBuilder::new().merge(left, right, conflict_policy)
+    }
+
+    /// Serializes the compiled automaton, including its links and replacements, into a versioned binary format protected by a checksum
+    /// Loading it back with from_bytes is much faster than building the automaton again
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.automaton.to_bytes()
+    }
+
+    /// Loads an automaton serialized with to_bytes. Bytes written by an incompatible version, truncated or corrupted are rejected with an error
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            automaton: Arc::new(AcAutomaton::from_bytes(bytes)?),
+        })
+    }
+
+    /// Uses an automaton serialized with to_bytes in place, without copying or parsing its tables, e.g. from a memory-mapped file :
+    /// several processes mapping the same file share a single read-only copy of the dictionary.
+    /// The bytes must be aligned on 8 bytes in memory (as a memory-mapped file always is), and are fully checked before being used.
+    /// They are resolved once with as_ref : the source must keep giving the same bytes, as a Vec, a boxed slice or a memory map do
+    pub fn from_shared_bytes<B>(bytes: B) -> Result<Self, DeserializeError>
+    where
+        B: AsRef<[u8]> + Send + Sync + 'static
+    {
+        Ok(Self {
+            automaton: Arc::new(AcAutomaton::from_shared_bytes(Arc::new(bytes))?),
+        })
+    }
+
+    /// Amount of states merged away when the automaton was built with minimization enabled, 0 otherwise
+    pub fn states_removed(&self) -> usize {
+        self.automaton.states_removed as usize
+    }
+
+    /// Obtain a Replacer over this automaton, to perform the replacements chunk by chunk without any I/O (available without the std feature)
+    pub fn into_replacer(self) -> Replacer {
+        Replacer::new(self)
+    }
+
+    /// Obtain AhoCorasickAsyncReader wrapping the original source. Reading from this new reader will yield output with replaced data
+    #[cfg(feature = "std")]
+    pub fn into_reader<R: AsyncRead>(self, source: R) -> AhoCorasickAsyncReader<R> {
+        AhoCorasickAsyncReader::new(self, source)
+    }
+
+    /// Obtain AhoCorasickAsyncWriter wrapping the original sink. Writing to this new writer will perform the replacements before sending the bytes to your sink
+    #[cfg(feature = "std")]
+    pub fn into_writer<W: AsyncWrite>(self, sink: W) -> AhoCorasickAsyncWriter<W> {
+        AhoCorasickAsyncWriter::new(self, sink)
+    }
+
+    /// Read all data from the reader, perform the replacements, and write to the writer
+    /// It is implemented using AhoCorasickAsyncWriter, but either works
+    #[cfg(feature = "std")]
+    pub async fn try_stream_replace_all<R, W>(self, reader: R, writer: W, buffer_size: usize) -> Result<(), std::io::Error>
+    where 
+        R: AsyncRead,
+        W: AsyncWrite
+    {
+        let mut buffer = vec![b'\0'; buffer_size];
+        let ac_writer = self.into_writer(writer);
+
+        let mut pinned_reader = Box::pin(reader);
+        let mut pinned_writer = Box::pin(ac_writer);
+        loop {
+            let bytes_read = pinned_reader.read(&mut buffer).await?;
+            if bytes_read == 0 {
+                pinned_writer.close().await?;
+                break;
+            } else {
+                pinned_writer.write_all(&buffer[..bytes_read]).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Support of the code generated by static_replacer! (in aho-corasick-async-macros). Not part of the public API
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod __private {
+    use std::sync::Arc;
+    use crate::{AhoCorasick, automaton::AcAutomaton};
+    pub use std::sync::OnceLock;
+
+    /// Bytes of an automaton serialized at compile time, aligned so that their tables can be used in place
+    #[repr(C, align(8))]
+    pub struct Aligned<const N: usize>(pub [u8; N]);
+
+    /// Automaton using the tables embedded in the binary in place. Its tables are validated and its small header is allocated once, at first use
+    pub fn static_automaton(cell: &'static OnceLock<AhoCorasick>, bytes: &'static [u8]) -> AhoCorasick {
+        cell.get_or_init(|| AhoCorasick {
+            automaton: Arc::new(
+                AcAutomaton::from_static_bytes(bytes).expect("static_replacer! was expanded by an incompatible version of aho-corasick-async-macros")
+            ),
+        }).clone()
+    }
+}
@@ -0,0 +1,146 @@
+use core::{cmp::Ordering, fmt::Display, iter::Peekable};
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use crate::{AhoCorasick, automaton::{AcAutomaton, StateId}, builder::AhoCorasickBuilder};
+
+/// Word of the dictionary with its replacement, as given to the builder
+type Entry = (Vec<u8>, Option<Vec<u8>>);
+
+/// What to do when both automatons being merged have the same word, with different replacements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The merge fails with the first conflicting word
+    Error,
+    /// The replacement of the left automaton is kept
+    PreferLeft,
+    /// The replacement of the right automaton is kept
+    PreferRight,
+}
+
+/// Error returned by merge with ConflictPolicy::Error : the word has a different replacement (or is protected) in each automaton
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub word: Vec<u8>,
+    pub left: Option<Vec<u8>>,
+    pub right: Option<Vec<u8>>,
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let replacement = |replacement: &Option<Vec<u8>>| match replacement {
+            Some(replacement) => format!("{:?}", String::from_utf8_lossy(replacement)),
+            None => "no replacement".to_string(),
+        };
+        write!(
+            f, "conflicting replacements for {:?} : {} on the left, {} on the right",
+            String::from_utf8_lossy(&self.word), replacement(&self.left), replacement(&self.right)
+        )
+    }
+}
+
+impl core::error::Error for MergeConflict {}
+
+impl AhoCorasickBuilder {
+    /// Builds one automaton with the words of both, using this configuration. The words are read back from the compiled automatons,
+    /// so the original lists are not needed. When a word has different replacements in both, the conflict policy decides the outcome
+    pub fn merge(&self, left: &AhoCorasick, right: &AhoCorasick, conflict_policy: ConflictPolicy) -> Result<AhoCorasick, MergeConflict> {
+        if conflict_policy == ConflictPolicy::Error {
+            // Checked beforehand, so that nothing is built in case of conflict
+            let conflict = Join::new(&left.automaton, &right.automaton).find_map(|entries| match entries {
+                (Some((word, left)), Some((_, right))) if left != right => Some(MergeConflict { word, left, right }),
+                _ => None
+            });
+            if let Some(conflict) = conflict {
+                return Err(conflict);
+            }
+        }
+        // Both word lists come sorted, and so does their union : the builder takes its fast path
+        Ok(self.build_from_iter(Join::new(&left.automaton, &right.automaton).map(|entries| match entries {
+            (Some(left), Some(_)) if conflict_policy == ConflictPolicy::PreferLeft => left,
+            (left, right) => right.or(left).expect("Logic error : the join always gives at least one side")
+        })))
+    }
+}
+
+impl AcAutomaton {
+    /// All the words of the dictionary with their replacements, sorted in byte order
+    pub(crate) fn words(&self) -> Words<'_> {
+        let mut transitions = self.transitions(StateId::ROOT);
+        transitions.reverse();
+        Words {
+            automaton: self,
+            // Words are only matched below root, but an empty word given to the builder is still kept in the dictionary
+            root: self.is_word(StateId::ROOT).then(|| (Vec::new(), self.replacement(StateId::ROOT).map(<[u8]>::to_vec))),
+            path: Vec::new(),
+            stack: Vec::from([transitions]),
+        }
+    }
+}
+
+/// Depth-first walk of the trie transitions, in byte order. The stack is explicit, as the deepest states are as deep as the longest word
+pub(crate) struct Words<'a> {
+    automaton: &'a AcAutomaton,
+    root: Option<Entry>,
+    path: Vec<u8>, // Bytes leading to the current state
+    stack: Vec<Vec<(u8, StateId)>>, // For each state of the path : its transitions remaining to walk (reversed)
+}
+
+impl Iterator for Words<'_> {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        if let Some(root) = self.root.take() {
+            return Some(root);
+        }
+        loop {
+            match self.stack.last_mut()?.pop() {
+                Some((val, next)) => {
+                    self.path.push(val);
+                    let mut transitions = self.automaton.transitions(next);
+                    transitions.reverse();
+                    self.stack.push(transitions);
+                    if self.automaton.is_match(next) {
+                        // A word comes before all the words it is a prefix of
+                        return Some((self.path.clone(), self.automaton.replacement(next).map(<[u8]>::to_vec)));
+                    }
+                },
+                None => {
+                    self.stack.pop();
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Sorted union of the words of two automatons. A word present in both comes with both entries
+struct Join<'a> {
+    left: Peekable<Words<'a>>,
+    right: Peekable<Words<'a>>,
+}
+
+impl<'a> Join<'a> {
+    fn new(left: &'a AcAutomaton, right: &'a AcAutomaton) -> Self {
+        Self {
+            left: left.words().peekable(),
+            right: right.words().peekable(),
+        }
+    }
+}
+
+impl Iterator for Join<'_> {
+    type Item = (Option<Entry>, Option<Entry>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.left.peek(), self.right.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((left, _)), Some((right, _))) => left.cmp(right),
+        };
+        Some(match order {
+            Ordering::Less => (self.left.next(), None),
+            Ordering::Greater => (None, self.right.next()),
+            Ordering::Equal => (self.left.next(), self.right.next()),
+        })
+    }
+}
@@ -0,0 +1,188 @@
+use core::cmp::Ordering;
+use alloc::{vec, vec::Vec};
+use crate::{automaton::{AcAutomaton, StateId}, compile::Draft};
+
+/// Merges the states having identical futures, e.g. the common endings of word inflections : a state may then be the target of
+/// several transitions. The tables of the Draft are rewritten in place, only the refinement tables (8 bytes per state) being allocated.
+/// Expects dense and sparse rows in state order : the DFA and the double array are built afterwards
+///
+/// Two states are equivalent when they have the same depth (the amount of bytes kept pending by the streams), the same word
+/// and replacement, equivalent suffix links, and equivalent transitions for the same bytes : streams going through either of them
+/// produce the exact same output. The classes are found by refining a partition until it is stable (Moore's algorithm), each round
+/// sorting the states by what they are refined on
+pub(crate) struct Minimization {
+    order: Vec<u32>, // States sorted by their last refinement key
+    classes: Vec<u32>, // Class of each state
+    class_count: usize,
+}
+
+impl Minimization {
+    /// Initial partition of the states
+    pub(crate) fn new(draft: &Draft) -> Self {
+        let mut order: Vec<u32> = (0..draft.states.len() as u32).collect();
+        let mut classes: Vec<u32> = vec![0; draft.states.len()];
+        let boundaries = sort_boundaries(&mut order, |a, b| draft.initial_key(a).cmp(&draft.initial_key(b)));
+        let class_count = number_classes(&order, &boundaries, &mut classes);
+        Self { order, classes, class_count }
+    }
+
+    /// Refines the partition once. Returns false if it was already stable
+    pub(crate) fn refine(&mut self, draft: &Draft) -> bool {
+        // Refining never merges classes, so the partition is stable as soon as no class was split (or every state has its own class)
+        if self.class_count == draft.states.len() {
+            return false;
+        }
+        let classes = &self.classes;
+        let boundaries = sort_boundaries(&mut self.order, |a, b| {
+            let key = |state: u32| (classes[state as usize], classes[draft.states[state as usize].suffix_link.index()]);
+            key(a).cmp(&key(b)).then_with(|| {
+                let row = |state: u32| draft.row(state).map(|(class, next)| (class, classes[next.index()]));
+                row(a).cmp(row(b))
+            })
+        });
+        let refined = number_classes(&self.order, &boundaries, &mut self.classes);
+        let split = refined > self.class_count;
+        self.class_count = refined;
+        split
+    }
+
+    /// Amount of classes found so far
+    pub(crate) fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    /// Keeps the first state of each class, once the partition is stable
+    pub(crate) fn merge(self, draft: &mut Draft) {
+        draft.merge(self.classes, self.order);
+    }
+}
+
+impl Draft {
+    /// What the states are first partitioned on : their depth, and whether they are a word with its replacement
+    fn initial_key(&self, state: u32) -> (u32, Option<Option<&[u8]>>) {
+        let this = &self.states[state as usize];
+        let replacement = (this.word != AcAutomaton::NOT_WORD).then(|| match self.replacement_bounds[this.word as usize] {
+            AcAutomaton::NO_REPLACEMENT => None,
+            [start, end] => Some(&self.replacement_bytes[start as usize..end as usize])
+        });
+        (this.depth, replacement)
+    }
+
+    /// Keeps the first state of each class, moving it, its row and its replacement down over the merged ones. The classes are
+    /// renumbered in the order of their first state, so they are still in breadth-first order : shallower states first, as needed by
+    /// the DFA. The spare table is reused for the renumbering
+    fn merge(&mut self, mut classes: Vec<u32>, mut spare: Vec<u32>) {
+        spare.fill(u32::MAX);
+        let mut next_class = 0;
+        for class in classes.iter_mut() {
+            if spare[*class as usize] == u32::MAX {
+                spare[*class as usize] = next_class;
+                next_class += 1;
+            }
+            *class = spare[*class as usize];
+        }
+        // Once renumbered, a state is the first of its class if its class is the next one
+        let is_first = |state: usize, kept: usize| classes[state] as usize == kept;
+
+        // The words keep their order, only the words of the kept states remaining. The replacements are in word order
+        // (see Trie::compact_words), so they only move down
+        let words = &mut spare;
+        words.fill(AcAutomaton::NOT_WORD);
+        let mut kept = 0;
+        for (state, this) in self.states.iter().enumerate() {
+            if is_first(state, kept) {
+                kept += 1;
+                if this.word != AcAutomaton::NOT_WORD {
+                    words[this.word as usize] = 0;
+                }
+            }
+        }
+        let (mut word_count, mut end) = (0, 0);
+        for (word, renumbered) in words.iter_mut().enumerate().take(self.replacement_bounds.len()) {
+            if *renumbered == AcAutomaton::NOT_WORD {
+                continue;
+            }
+            *renumbered = word_count as u32;
+            self.replacement_bounds[word_count] = match self.replacement_bounds[word] {
+                AcAutomaton::NO_REPLACEMENT => AcAutomaton::NO_REPLACEMENT,
+                [start, stop] => {
+                    let start_moved = end;
+                    self.replacement_bytes.copy_within(start as usize..stop as usize, start_moved as usize);
+                    end += stop - start;
+                    [start_moved, end]
+                }
+            };
+            word_count += 1;
+        }
+        self.replacement_bounds.truncate(word_count);
+        self.replacement_bytes.truncate(end as usize);
+
+        // The rows are in state order too : the dense and sparse pools are compacted the same way
+        let class = |state: StateId| StateId(classes[state.index()]);
+        let alphabet_len = self.byte_classes.alphabet_len();
+        let (mut dense_end, mut sparse_end) = (0, 0);
+        let mut kept = 0;
+        for state in 0..self.states.len() {
+            if !is_first(state, kept) {
+                continue;
+            }
+            let mut this = self.states[state];
+            this.suffix_link = class(this.suffix_link);
+            if this.output_link != StateId::FAIL {
+                this.output_link = class(this.output_link);
+            }
+            if this.word != AcAutomaton::NOT_WORD {
+                this.word = words[this.word as usize];
+            }
+            let row_start = this.row_start as usize;
+            if this.row_len == AcAutomaton::DENSE_ROW {
+                self.dense.copy_within(row_start..row_start + alphabet_len, dense_end);
+                for next in self.dense[dense_end..dense_end + alphabet_len].iter_mut().filter(|next| **next != StateId::FAIL) {
+                    *next = class(*next);
+                }
+                this.row_start = dense_end as u32;
+                dense_end += alphabet_len;
+            } else {
+                let row_len = this.row_len as usize;
+                self.sparse_classes.copy_within(row_start..row_start + row_len, sparse_end);
+                self.sparse_next.copy_within(row_start..row_start + row_len, sparse_end);
+                for next in self.sparse_next[sparse_end..sparse_end + row_len].iter_mut() {
+                    *next = class(*next);
+                }
+                this.row_start = sparse_end as u32;
+                sparse_end += row_len;
+            }
+            self.states[kept] = this;
+            kept += 1;
+        }
+        self.states_removed = (self.states.len() - kept) as u32;
+        self.states.truncate(kept);
+        self.dense.truncate(dense_end);
+        self.sparse_classes.truncate(sparse_end);
+        self.sparse_next.truncate(sparse_end);
+    }
+}
+
+/// Sorts the states with the comparison, and returns the bitset of the positions where a new class starts in the sorted order
+fn sort_boundaries(order: &mut [u32], mut compare: impl FnMut(u32, u32) -> Ordering) -> Vec<u64> {
+    order.sort_unstable_by(|a, b| compare(*a, *b));
+    let mut boundaries = vec![0u64; order.len().div_ceil(64)];
+    for (position, pair) in order.windows(2).enumerate() {
+        if compare(pair[0], pair[1]) != Ordering::Equal {
+            boundaries[(position + 1) / 64] |= 1 << ((position + 1) % 64);
+        }
+    }
+    boundaries
+}
+
+/// Gives each state the class of its group in the sorted order. Returns the amount of classes
+fn number_classes(order: &[u32], boundaries: &[u64], classes: &mut [u32]) -> usize {
+    let mut class = 0;
+    for (position, state) in order.iter().enumerate() {
+        if boundaries[position / 64] & (1 << (position % 64)) != 0 {
+            class += 1;
+        }
+        classes[*state as usize] = class;
+    }
+    class as usize + 1
+}
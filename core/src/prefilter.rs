@@ -0,0 +1,118 @@
+use alloc::{boxed::Box, vec::Vec};
+use crate::automaton::{AcAutomaton, StateId};
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+use crate::teddy::Teddy;
+
+/// Fast search of the next byte which can start a match. At root, any other byte leaves the state at root and is written as is,
+/// so the streams can copy the whole span before the next candidate in bulk, instead of walking the automaton byte by byte
+///
+/// With few words, the search looks at the first bytes of the words rather than at their first byte only (see Teddy). Skipping a position
+/// where none of the m first bytes of a word start is exact as long as no word is shorter than m : from there, the automaton can only
+/// go through states shorter than m, which are not words, and it reaches the same state as when restarting from root at the next candidate
+#[derive(Debug, Clone)]
+pub(crate) enum Prefilter {
+    /// Every byte can start a match, or the start bytes are too common in usual inputs for skipping to pay off
+    Disabled,
+    /// No byte can start a match (empty dictionary) : everything is copied as is
+    NoStartByte,
+    One(u8),
+    Two(u8, u8),
+    Three(u8, u8, u8),
+    /// More start bytes, all rare enough so that the spans between them are long
+    ByteSet(Box<[bool; 256]>),
+    /// Few words, all at least 2 bytes long : packed search of their 2 or 3 first bytes
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    Teddy(Box<Teddy>),
+}
+
+impl Prefilter {
+    /// Up to this sum of estimated frequencies (in per mille), the start bytes are rare enough for a byte set scan : on average,
+    /// the skipped spans are at least 4 bytes long
+    const MAX_BYTE_SET_FREQUENCY: u32 = 250;
+
+    /// Prefilter over the bytes having a transition from root
+    pub(crate) fn new(automaton: &AcAutomaton) -> Self {
+        #[cfg(all(target_arch = "x86_64", feature = "std"))]
+        if let Some(teddy) = Self::prefixes(automaton).and_then(|prefixes| Teddy::new(&prefixes)) {
+            return Self::Teddy(Box::new(teddy));
+        }
+        let start_bytes: Vec<u8> = (0..=255u8)
+            .filter(|byte| automaton.next_state(StateId::ROOT, *byte) != StateId::ROOT)
+            .collect();
+        match start_bytes[..] {
+            [] => Self::NoStartByte,
+            // memchr is fast enough even for common bytes
+            [byte] => Self::One(byte),
+            [byte1, byte2] => Self::Two(byte1, byte2),
+            [byte1, byte2, byte3] => Self::Three(byte1, byte2, byte3),
+            _ if start_bytes.iter().map(|byte| Self::frequency(*byte)).sum::<u32>() <= Self::MAX_BYTE_SET_FREQUENCY => {
+                let mut set = Box::new([false; 256]);
+                for byte in start_bytes {
+                    set[byte as usize] = true;
+                }
+                Self::ByteSet(set)
+            },
+            _ => Self::Disabled
+        }
+    }
+
+    /// Amount of leading bytes of the input which can not start a match
+    #[inline(always)]
+    pub(crate) fn skippable(&self, input: &[u8]) -> usize {
+        let found = match self {
+            Self::Disabled => return 0,
+            Self::NoStartByte => return input.len(),
+            Self::One(byte) => memchr::memchr(*byte, input),
+            Self::Two(byte1, byte2) => memchr::memchr2(*byte1, *byte2, input),
+            Self::Three(byte1, byte2, byte3) => memchr::memchr3(*byte1, *byte2, *byte3, input),
+            Self::ByteSet(set) => input.iter().position(|byte| set[*byte as usize]),
+            #[cfg(all(target_arch = "x86_64", feature = "std"))]
+            Self::Teddy(teddy) => return teddy.find(input),
+        };
+        found.unwrap_or(input.len())
+    }
+
+    /// All the distinct paths of 3 bytes from root (or of the length of the shortest word, if shorter), sorted.
+    /// None if there are too many of them for a packed search
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    fn prefixes(automaton: &AcAutomaton) -> Option<Vec<Vec<u8>>> {
+        let shortest_word = (1..automaton.states.len())
+            .map(|state| StateId(state as u32))
+            .filter(|state| automaton.is_match(*state))
+            .map(|state| automaton.depth(state))
+            .min()?;
+        let len = shortest_word.min(3);
+        let mut prefixes: Vec<Vec<u8>> = Vec::new();
+        let mut to_walk: Vec<(Vec<u8>, StateId)> = Vec::from([(Vec::new(), StateId::ROOT)]);
+        while let Some((prefix, state)) = to_walk.pop() {
+            if prefix.len() == len {
+                prefixes.push(prefix);
+                if prefixes.len() > Teddy::MAX_PREFIXES {
+                    return None;
+                }
+                continue;
+            }
+            for (byte, next) in automaton.transitions(state) {
+                to_walk.push(([&prefix[..], &[byte]].concat(), next));
+            }
+        }
+        prefixes.sort_unstable();
+        Some(prefixes)
+    }
+
+    /// Rough frequency of the byte in usual text (natural languages, markup, code), in per mille. This is the rare-byte heuristic
+    /// deciding whether a set of start bytes is worth scanning for
+    fn frequency(byte: u8) -> u32 {
+        match byte {
+            b' ' => 150,
+            b'e' => 90,
+            b't' | b'a' | b'o' => 60,
+            b'i' | b'n' | b's' | b'r' | b'h' => 50,
+            b'l' | b'd' | b'c' | b'u' | b'm' => 30,
+            b'a'..=b'z' | b'\n' => 15,
+            b'A'..=b'Z' | b'0'..=b'9' | b'!'..=b'~' => 5,
+            b'\t' | b'\r' | 0x80..=0xFF => 2, // Non-ASCII bytes only come in multibyte UTF-8 sequences, spread over many values
+            _ => 0
+        }
+    }
+}
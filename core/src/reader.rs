@@ -0,0 +1,125 @@
+use std::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+use crate::{AhoCorasick, LazyDfaStats, replacer::{Replacer, ReplacerOutput}};
+
+// Wrapper over an AsyncRead. Reading from AhoCorasickAsyncReader polls replaced results
+pin_project! {
+    pub struct AhoCorasickAsyncReader<R> {
+        #[pin]
+        source: R,
+        replacer: Replacer, // Per-stream replacement state over the shared automaton
+        buffer: Vec<u8>, // Used to buffer initially read bytes (before replacements)
+        pending_write_buffer: Vec<u8>, // Buffer holding the data ready to be written. Might need to wait until next chunk
+        pending_write_start: usize, // Amount of bytes of pending_write_buffer already written
+    }
+}
+
+impl<R: AsyncRead> AhoCorasickAsyncReader<R> {
+    pub fn new(ac: AhoCorasick, source: R) -> Self {
+        AhoCorasickAsyncReader {
+            source,
+            replacer: Replacer::new(ac),
+            buffer: Vec::new(),
+            pending_write_buffer: Vec::new(),
+            pending_write_start: 0,
+        }
+    }
+
+    /// Hit and miss counters of the lazy DFA cache of this stream. None if the automaton was not built in lazy DFA mode
+    pub fn lazy_dfa_stats(&self) -> Option<LazyDfaStats> {
+        self.replacer.lazy_dfa_stats()
+    }
+}
+
+/// Output filling the buffer given to poll_read : the bytes which do not fit in it are kept in the pending buffer for the next reads
+struct ReadOutput<'a> {
+    buf: &'a mut [u8],
+    idx: usize,
+    overflow: &'a mut Vec<u8>,
+}
+
+impl ReplacerOutput for ReadOutput<'_> {
+    #[inline(always)]
+    fn write(&mut self, bytes: &[u8]) {
+        let fitting = bytes.len().min(self.buf.len() - self.idx);
+        self.buf[self.idx..self.idx + fitting].copy_from_slice(&bytes[..fitting]);
+        self.idx += fitting;
+        self.overflow.extend_from_slice(&bytes[fitting..]);
+    }
+}
+
+impl<R> AsyncRead for AhoCorasickAsyncReader<R>
+where
+    R: AsyncRead
+{
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let this = self.as_mut().project();
+        if this.buffer.len() < buf.len() {
+            this.buffer.resize(buf.len(), b'\0');
+        }
+        // First, write pending buffer if any
+        let pending = &this.pending_write_buffer[*this.pending_write_start..];
+        let mut write_idx = pending.len().min(buf.len());
+        buf[..write_idx].copy_from_slice(&pending[..write_idx]);
+        *this.pending_write_start += write_idx;
+        if write_idx >= buf.len() {
+            // Pending buffer had enough data to fully fill buf => no need to poll from source, wait for next read
+            return Poll::Ready(Ok(write_idx));
+        }
+        // The pending buffer has been fully written
+        this.pending_write_buffer.clear();
+        *this.pending_write_start = 0;
+        match this.source.poll_read(cx, this.buffer) {
+            Poll::Ready(result) => {
+                match result {
+                    Ok(size) => {
+                        let mut output = ReadOutput {
+                            buf,
+                            idx: write_idx,
+                            overflow: this.pending_write_buffer,
+                        };
+                        if size == 0 {
+                            // End reached - discard potential buffer
+                            this.replacer.finish(&mut output);
+                        }
+                        this.replacer.replace(&this.buffer[..size], &mut output);
+                        write_idx = output.idx;
+                        if write_idx > 0 {
+                            // Something has been written
+                            Poll::Ready(Ok(write_idx))
+                        } else if size > 0 {
+                            // Special cases handling : a non-empty chunk has been read from the source, however nothing has been written
+                            // Identified cases where this might happen :
+                            // 1. When the pattern exceeds the chunk size, and is fully buffered in potential_buffer waiting to be replaced or discarded
+                            // 2. When the chunk fully matches a pattern, and the replacement is an empty string (very specific)
+                            //
+                            // We cannot respond with Ok(0), which would mean end of read, so we simply request a new poll immediately,
+                            // and proceed reading more chunks from the source
+                            cx.waker().wake_by_ref();
+                            Poll::Pending
+                        } else {
+                            // Nothing left to write
+                            Poll::Ready(Ok(0))
+                        }
+                    },
+                    Err(err) => {
+                        Poll::Ready(Err(err))
+                    }
+                }
+            },
+            Poll::Pending => {
+                if write_idx > 0 {
+                    // While waiting for the source, if some bytes have already been written from pending buffer, we can return them immediately to speed things up
+                    Poll::Ready(Ok(write_idx))
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::vec::Vec;
+use crate::{AhoCorasick, LazyDfaStats, automaton::AcCursor};
+
+/// Destination of the replaced bytes. They always come as whole spans : literal runs of the input, or replacements.
+/// Implemented for `Vec<u8>`, and can be implemented for any other buffer, e.g. a fixed-size one on an embedded target
+pub trait ReplacerOutput {
+    fn write(&mut self, bytes: &[u8]);
+}
+
+impl ReplacerOutput for Vec<u8> {
+    #[inline(always)]
+    fn write(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Replacement core shared by the reader and the writer, without any I/O : chunks of input go in, replaced bytes go out.
+/// It only needs alloc, so it is available without the std feature, to perform the replacements over any transport
+/// Within a chunk, the bytes are never moved one by one. The literal run (bytes known to be written as is) and the potential match
+/// are tracked as ranges of the chunk, and only copied when a replacement is written or at the end of the chunk
+/// Only the beginning of a potential match which is still undecided at the end of a chunk is copied aside, until the next chunk
+#[derive(Debug)]
+pub struct Replacer {
+    cursor: AcCursor, // Per-stream state over the shared automaton
+    potential_buffer: Vec<u8>, // Beginning of a potential match, carried over from the previous chunks
+}
+
+impl Replacer {
+    pub fn new(ac: AhoCorasick) -> Self {
+        Self {
+            cursor: AcCursor::new(ac.automaton),
+            potential_buffer: Vec::new(),
+        }
+    }
+
+    /// Hit and miss counters of the lazy DFA cache. None if the automaton was not built in lazy DFA mode
+    pub fn lazy_dfa_stats(&self) -> Option<LazyDfaStats> {
+        self.cursor.lazy_dfa_stats()
+    }
+
+    /// Bytes held back as the beginning of a potential match, waiting for the next chunk to be decided
+    pub fn potential(&self) -> &[u8] {
+        &self.potential_buffer
+    }
+
+    /// Forgets the potential match, once it has been written by the caller at the end of the stream
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : AhoCorasickAsyncReader
+    pub(crate) fn clear_potential(&mut self) {
+        self.potential_buffer.clear();
+    }
+
+    /// End of the stream : the potential match can not be completed anymore, so it is written as is
+    pub fn finish(&mut self, output: &mut impl ReplacerOutput) {
+        output.write(&self.potential_buffer);
+        self.potential_buffer.clear();
+    }
+
+    /// Processes a chunk of input. Every byte is either written to the output (as is, or replaced), or kept as a potential match for the next chunk
+    pub fn replace(&mut self, input: &[u8], output: &mut impl ReplacerOutput) {
+        // The input is split as : [written | literal run | potential match | unread]. The potential match may start in the potential buffer,
+        // in which case it is the only one not to be empty : the bytes of the buffer come first in the output
+        let mut literal_start = 0usize;
+        let mut potential_start = 0usize;
+        let mut read_idx = 0usize;
+        while read_idx < input.len() {
+            // At root, the bytes which can not start a match join the literal run, without walking the automaton over them
+            let skipped = self.cursor.skippable(&input[read_idx..]);
+            if skipped > 0 {
+                read_idx += skipped;
+                potential_start = read_idx;
+                continue;
+            }
+            self.cursor.next_state(&input[read_idx]);
+            read_idx += 1;
+            // Either we followed a potential word, or we jumped to a different branch following the suffix link (possibly root)
+            // The potential match keeps the amount of last bytes equal to the new state node depth : the bytes before it are discarded (written as is)
+            let potential_len = self.potential_buffer.len() + read_idx - potential_start;
+            let mut discarded = potential_len.saturating_sub(self.cursor.state_depth());
+            if discarded > 0 && !self.potential_buffer.is_empty() {
+                let carried = discarded.min(self.potential_buffer.len());
+                output.write(&self.potential_buffer[..carried]);
+                self.potential_buffer.drain(..carried);
+                discarded -= carried;
+            }
+            potential_start += discarded;
+            if !self.cursor.is_state_root() && self.cursor.is_state_word() {
+                // Minimal size word detected => replacement. Currently, the only mode is "first found first replaced", even in case a larger overlapping replacement would've been possible
+                if let Some(replacement) = self.cursor.state_replacement() {
+                    // Replacement is given by the automaton node : the literal run is written, and the potential match is dropped
+                    output.write(&input[literal_start..potential_start]);
+                    self.potential_buffer.clear();
+                    output.write(replacement);
+                    literal_start = read_idx;
+                } else {
+                    // We have reached a protected word : it is written as is. The state will be reset in all cases, as if the word had been found
+                    output.write(&self.potential_buffer);
+                    self.potential_buffer.clear();
+                }
+                potential_start = read_idx;
+                self.cursor.reset_state();
+            }
+        }
+        output.write(&input[literal_start..potential_start]);
+        self.potential_buffer.extend_from_slice(&input[potential_start..]);
+    }
+}
@@ -0,0 +1,479 @@
+use core::{fmt::Display, ops::Range};
+use alloc::{vec, vec::Vec};
+use crate::{automaton::{AcAutomaton, State, StateId}, builder::AhoCorasickBuilder, byte_classes::ByteClasses, prefilter::Prefilter, table::{SharedBytes, Table}};
+
+/// Binary format of a compiled automaton
+///
+/// All the integers are little-endian u32. The file starts with a fixed header, followed by the 256-entry byte classes table,
+/// and then by the tables of the automaton, each one starting at an offset aligned on 8 bytes :
+///
+/// | Offset | Content                                                                  |
+/// |--------|--------------------------------------------------------------------------|
+/// | 0      | Magic bytes `ACASYNC\0`                                                  |
+/// | 8      | Format version                                                           |
+/// | 12     | CRC-32 checksum of everything following it                               |
+/// | 16     | Counts : alphabet_len, states, dense, check, sparse, words, replacement bytes, dfa, then states removed by minimization, lazy DFA cache size, flags, reserved |
+/// | 64     | Byte classes, 256 bytes                                                  |
+/// | 320    | States (6 u32 each), dense rows, check, sparse classes (u8), sparse targets, replacement bounds (2 u32 each), replacement bytes (u8), dfa |
+pub(crate) const MAGIC: [u8; 8] = *b"ACASYNC\0";
+/// Bumped on every change of the layout, so that files written by another version are rejected instead of being misread
+pub(crate) const VERSION: u32 = 3;
+const CHECKSUM_OFFSET: usize = 12;
+const COUNTS_OFFSET: usize = 16;
+const CLASSES_OFFSET: usize = 64;
+pub(crate) const HEADER_LEN: usize = CLASSES_OFFSET + 256;
+pub(crate) const SECTION_ALIGN: usize = 8;
+const STATE_LEN: usize = core::mem::size_of::<State>();
+
+/// Error returned when loading an automaton from bytes which are not a valid serialized automaton
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeserializeError {
+    /// The bytes do not start with the expected magic bytes : this is not a serialized automaton
+    InvalidMagic,
+    /// The automaton was serialized by an incompatible version of this crate
+    UnsupportedVersion(u32),
+    /// The content does not match its checksum : the bytes are truncated or corrupted
+    ChecksumMismatch,
+    /// The bytes are shorter than what the header announces
+    Truncated,
+    /// The tables are not consistent with each other
+    Malformed(&'static str),
+    /// The bytes can not be used in place, as the tables are not aligned in memory
+    Unaligned,
+    /// The bytes can not be used in place on a big-endian platform (they can still be copied with from_bytes)
+    UnsupportedEndianness,
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "not a serialized automaton (invalid magic bytes)"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported format version {} (expected {})", version, VERSION),
+            Self::ChecksumMismatch => write!(f, "checksum mismatch, the serialized automaton is corrupted"),
+            Self::Truncated => write!(f, "the serialized automaton is truncated"),
+            Self::Malformed(reason) => write!(f, "malformed automaton : {}", reason),
+            Self::Unaligned => write!(f, "the serialized automaton is not aligned on {} bytes in memory", SECTION_ALIGN),
+            Self::UnsupportedEndianness => write!(f, "the serialized automaton can only be used in place on little-endian platforms"),
+        }
+    }
+}
+
+impl core::error::Error for DeserializeError {}
+
+/// Position of each table in the serialized bytes, computed from the counts of the header
+#[derive(Debug, Clone)]
+pub(crate) struct Layout {
+    pub(crate) alphabet_len: usize,
+    pub(crate) states: Range<usize>,
+    pub(crate) dense: Range<usize>,
+    pub(crate) check: Range<usize>,
+    pub(crate) sparse_classes: Range<usize>,
+    pub(crate) sparse_next: Range<usize>,
+    pub(crate) replacement_bounds: Range<usize>,
+    pub(crate) replacement_bytes: Range<usize>,
+    pub(crate) dfa: Range<usize>,
+    pub(crate) len: usize,
+    pub(crate) states_removed: u32, // Not a table length, only reported in the stats
+    pub(crate) lazy_dfa_cache: u32, // Not a table length either
+    pub(crate) flags: u32,
+}
+
+impl Layout {
+    /// Checks the header and the checksum of the bytes, and computes where each table is. Does not check the content of the tables
+    pub(crate) fn read(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let layout = Self::read_header(bytes)?;
+        if read_u32(bytes, CHECKSUM_OFFSET) != crc32(&bytes[CHECKSUM_OFFSET + 4..layout.len]) {
+            return Err(DeserializeError::ChecksumMismatch);
+        }
+        Ok(layout)
+    }
+
+    /// Same as read, without the checksum : only the fixed-size header is read
+    fn read_header(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+            return Err(DeserializeError::InvalidMagic);
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(DeserializeError::Truncated);
+        }
+        let version = read_u32(bytes, MAGIC.len());
+        if version != VERSION {
+            return Err(DeserializeError::UnsupportedVersion(version));
+        }
+        let count = |index: usize| read_u32(bytes, COUNTS_OFFSET + index * 4) as usize;
+        let mut layout = Self::new(count(0), count(1), count(2), count(3), count(4), count(5), count(6), count(7))
+            .ok_or(DeserializeError::Truncated)?;
+        layout.states_removed = count(8) as u32;
+        layout.lazy_dfa_cache = count(9) as u32;
+        layout.flags = count(10) as u32;
+        if bytes.len() < layout.len {
+            return Err(DeserializeError::Truncated);
+        }
+        Ok(layout)
+    }
+
+    /// Computes the layout from the table lengths. None in case of overflow
+    #[allow(clippy::too_many_arguments)]
+    fn new(alphabet_len: usize, states: usize, dense: usize, check: usize, sparse: usize, words: usize, replacement_bytes: usize, dfa: usize) -> Option<Self> {
+        let mut offset = HEADER_LEN;
+        let mut section = |len: Option<usize>| -> Option<Range<usize>> {
+            let start = offset;
+            let end = start.checked_add(len?)?;
+            offset = end.checked_next_multiple_of(SECTION_ALIGN)?;
+            Some(start..end)
+        };
+        let states = section(states.checked_mul(STATE_LEN))?;
+        let dense = section(dense.checked_mul(4))?;
+        let check = section(check.checked_mul(4))?;
+        let sparse_classes = section(Some(sparse))?;
+        let sparse_next = section(sparse.checked_mul(4))?;
+        let replacement_bounds = section(words.checked_mul(8))?;
+        let replacement_bytes = section(Some(replacement_bytes))?;
+        let dfa = section(dfa.checked_mul(4))?;
+        Some(Self {
+            alphabet_len,
+            states,
+            dense,
+            check,
+            sparse_classes,
+            sparse_next,
+            replacement_bounds,
+            replacement_bytes,
+            len: dfa.end,
+            dfa,
+            states_removed: 0,
+            lazy_dfa_cache: 0,
+            flags: 0,
+        })
+    }
+}
+
+impl AcAutomaton {
+    /// Serializes the automaton into the binary format described in this module
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let layout = Layout::new(
+            self.byte_classes.alphabet_len(),
+            self.states.len(),
+            self.dense.len(),
+            self.check.len(),
+            self.sparse_classes.len(),
+            self.replacement_bounds.len(),
+            self.replacement_bytes.len(),
+            self.dfa.len(),
+        ).expect("In-memory automaton sizes can not overflow");
+        let mut bytes = Vec::with_capacity(layout.len);
+        bytes.extend_from_slice(&MAGIC);
+        for value in [
+            VERSION,
+            0, // Checksum, filled at the end
+            self.byte_classes.alphabet_len() as u32,
+            self.states.len() as u32,
+            self.dense.len() as u32,
+            self.check.len() as u32,
+            self.sparse_classes.len() as u32,
+            self.replacement_bounds.len() as u32,
+            self.replacement_bytes.len() as u32,
+            self.dfa.len() as u32,
+            self.states_removed,
+            self.lazy_dfa_cache,
+            self.flags,
+            0, // Reserved
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(self.byte_classes.as_raw());
+
+        let pad = |bytes: &mut Vec<u8>| bytes.resize(bytes.len().next_multiple_of(SECTION_ALIGN), 0);
+        for state in self.states.iter() {
+            for value in [state.depth, state.suffix_link.0, state.output_link.0, state.word, state.row_start, state.row_len] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        pad(&mut bytes);
+        self.dense.iter().for_each(|next| bytes.extend_from_slice(&next.0.to_le_bytes()));
+        pad(&mut bytes);
+        self.check.iter().for_each(|owner| bytes.extend_from_slice(&owner.0.to_le_bytes()));
+        pad(&mut bytes);
+        bytes.extend_from_slice(&self.sparse_classes);
+        pad(&mut bytes);
+        self.sparse_next.iter().for_each(|next| bytes.extend_from_slice(&next.0.to_le_bytes()));
+        pad(&mut bytes);
+        self.replacement_bounds.iter().flatten().for_each(|bound| bytes.extend_from_slice(&bound.to_le_bytes()));
+        pad(&mut bytes);
+        bytes.extend_from_slice(&self.replacement_bytes);
+        pad(&mut bytes);
+        self.dfa.iter().for_each(|next| bytes.extend_from_slice(&next.0.to_le_bytes()));
+        debug_assert_eq!(bytes.len(), layout.len);
+
+        let checksum = crc32(&bytes[CHECKSUM_OFFSET + 4..]);
+        bytes[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 4].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Reads back an automaton serialized with to_bytes. The tables are copied, and fully checked before being used
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let layout = Layout::read(bytes)?;
+        let classes: [u8; 256] = bytes[CLASSES_OFFSET..HEADER_LEN].try_into().unwrap();
+        let byte_classes = ByteClasses::from_raw(classes, layout.alphabet_len)
+            .ok_or(DeserializeError::Malformed("invalid byte classes"))?;
+        let states: Vec<u32> = read_u32s(bytes, &layout.states).collect();
+        let replacement_bounds: Vec<u32> = read_u32s(bytes, &layout.replacement_bounds).collect();
+        let states = states.chunks_exact(6).map(|state| State {
+            depth: state[0],
+            suffix_link: StateId(state[1]),
+            output_link: StateId(state[2]),
+            word: state[3],
+            row_start: state[4],
+            row_len: state[5],
+        }).collect();
+        let mut ac = AcAutomaton {
+            states,
+            byte_classes,
+            dense: read_u32s(bytes, &layout.dense).map(StateId).collect(),
+            check: read_u32s(bytes, &layout.check).map(StateId).collect(),
+            sparse_classes: bytes[layout.sparse_classes.clone()].to_vec().into(),
+            sparse_next: read_u32s(bytes, &layout.sparse_next).map(StateId).collect(),
+            replacement_bounds: replacement_bounds.chunks_exact(2).map(|bounds| [bounds[0], bounds[1]]).collect(),
+            replacement_bytes: bytes[layout.replacement_bytes.clone()].to_vec().into(),
+            dfa: read_u32s(bytes, &layout.dfa).map(StateId).collect(),
+            states_removed: layout.states_removed,
+            lazy_dfa_cache: layout.lazy_dfa_cache,
+            flags: layout.flags,
+            prefilter: Prefilter::Disabled,
+        };
+        ac.validate()?;
+        ac.init_prefilter();
+        Ok(ac)
+    }
+
+    /// Uses the bytes of an automaton serialized with to_bytes in place : the tables are read directly from them, without being copied.
+    /// The bytes must start at an address aligned on 8 bytes (which is always the case for a memory-mapped file). The header and the
+    /// tables are fully checked before being used, malformed input is rejected with an error
+    pub(crate) fn from_shared_bytes(bytes: SharedBytes) -> Result<Self, DeserializeError> {
+        if cfg!(target_endian = "big") {
+            return Err(DeserializeError::UnsupportedEndianness);
+        }
+        let slice: &[u8] = (*bytes).as_ref();
+        let layout = Layout::read(slice)?;
+        if !(slice.as_ptr() as usize).is_multiple_of(SECTION_ALIGN) {
+            return Err(DeserializeError::Unaligned);
+        }
+        let classes: [u8; 256] = slice[CLASSES_OFFSET..HEADER_LEN].try_into().unwrap();
+        let byte_classes = ByteClasses::from_raw(classes, layout.alphabet_len)
+            .ok_or(DeserializeError::Malformed("invalid byte classes"))?;
+        let table = |range: Range<usize>| slice.get(range).ok_or(DeserializeError::Truncated);
+        let mut ac = AcAutomaton {
+            states: Table::shared(&bytes, table(layout.states)?).ok_or(DeserializeError::Unaligned)?,
+            byte_classes,
+            dense: Table::shared(&bytes, table(layout.dense)?).ok_or(DeserializeError::Unaligned)?,
+            check: Table::shared(&bytes, table(layout.check)?).ok_or(DeserializeError::Unaligned)?,
+            sparse_classes: Table::shared(&bytes, table(layout.sparse_classes)?).ok_or(DeserializeError::Unaligned)?,
+            sparse_next: Table::shared(&bytes, table(layout.sparse_next)?).ok_or(DeserializeError::Unaligned)?,
+            replacement_bounds: Table::shared(&bytes, table(layout.replacement_bounds)?).ok_or(DeserializeError::Unaligned)?,
+            replacement_bytes: Table::shared(&bytes, table(layout.replacement_bytes)?).ok_or(DeserializeError::Unaligned)?,
+            dfa: Table::shared(&bytes, table(layout.dfa)?).ok_or(DeserializeError::Unaligned)?,
+            states_removed: layout.states_removed,
+            lazy_dfa_cache: layout.lazy_dfa_cache,
+            flags: layout.flags,
+            prefilter: Prefilter::Disabled,
+        };
+        ac.validate()?;
+        ac.init_prefilter();
+        Ok(ac)
+    }
+
+    /// Uses the bytes embedded in the binary by static_replacer! in place. They were written by to_bytes during the compilation,
+    /// so the checksum is trusted, but the header and the tables are checked as with from_shared_bytes (the macro might come from another
+    /// version of the crate) : this is done once, at first use. On big-endian platforms, the tables are copied and checked as with from_bytes
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : static_replacer!
+    pub(crate) fn from_static_bytes(bytes: &'static [u8]) -> Result<Self, DeserializeError> {
+        if cfg!(target_endian = "big") {
+            return Self::from_bytes(bytes);
+        }
+        let layout = Layout::read_header(bytes)?;
+        let classes: [u8; 256] = bytes[CLASSES_OFFSET..HEADER_LEN].try_into().unwrap();
+        let byte_classes = ByteClasses::from_raw(classes, layout.alphabet_len)
+            .ok_or(DeserializeError::Malformed("invalid byte classes"))?;
+        let table = |range: Range<usize>| bytes.get(range).ok_or(DeserializeError::Truncated);
+        let mut ac = AcAutomaton {
+            states: Table::from_static(table(layout.states)?).ok_or(DeserializeError::Unaligned)?,
+            byte_classes,
+            dense: Table::from_static(table(layout.dense)?).ok_or(DeserializeError::Unaligned)?,
+            check: Table::from_static(table(layout.check)?).ok_or(DeserializeError::Unaligned)?,
+            sparse_classes: Table::from_static(table(layout.sparse_classes)?).ok_or(DeserializeError::Unaligned)?,
+            sparse_next: Table::from_static(table(layout.sparse_next)?).ok_or(DeserializeError::Unaligned)?,
+            replacement_bounds: Table::from_static(table(layout.replacement_bounds)?).ok_or(DeserializeError::Unaligned)?,
+            replacement_bytes: Table::from_static(table(layout.replacement_bytes)?).ok_or(DeserializeError::Unaligned)?,
+            dfa: Table::from_static(table(layout.dfa)?).ok_or(DeserializeError::Unaligned)?,
+            states_removed: layout.states_removed,
+            lazy_dfa_cache: layout.lazy_dfa_cache,
+            flags: layout.flags,
+            prefilter: Prefilter::Disabled,
+        };
+        ac.validate()?;
+        ac.init_prefilter();
+        Ok(ac)
+    }
+
+    /// Checks that the tables are consistent, so that matching can neither panic nor loop forever on a crafted input
+    pub(crate) fn validate(&self) -> Result<(), DeserializeError> {
+        let malformed = |reason: &'static str| Err(DeserializeError::Malformed(reason));
+        let state_count = self.states.len();
+        let alphabet_len = self.byte_classes.alphabet_len();
+        let is_state = |state: StateId| state.index() < state_count;
+        if state_count == 0 || self.states[StateId::ROOT.index()].depth != 0 {
+            return malformed("missing root state");
+        }
+        // Each stream allocates the lazy DFA cache : an unbounded size would let the bytes request any amount of memory
+        if self.lazy_dfa_cache as usize > AhoCorasickBuilder::MAX_LAZY_DFA_CACHE {
+            return malformed("invalid lazy DFA cache size");
+        }
+        if self.flags & !AcAutomaton::FLAG_NO_PREFILTER != 0 {
+            return malformed("unknown flags");
+        }
+        if !self.dfa.is_empty() && (self.dfa.len() != state_count * alphabet_len || !self.dfa.iter().all(|next| is_state(*next))) {
+            return malformed("invalid dfa table");
+        }
+        if !self.sparse_next.iter().all(|next| is_state(*next)) || !self.sparse_classes.iter().all(|class| (*class as usize) < alphabet_len) {
+            return malformed("invalid sparse transitions");
+        }
+        if !self.dense.iter().all(|next| *next == StateId::FAIL || is_state(*next)) {
+            return malformed("invalid dense transitions");
+        }
+        if !self.check.is_empty() && (self.check.len() != self.dense.len() || !self.check.iter().all(|owner| *owner == StateId::FAIL || is_state(*owner))) {
+            return malformed("invalid double array");
+        }
+        for [start, end] in self.replacement_bounds.iter().copied() {
+            if [start, end] != AcAutomaton::NO_REPLACEMENT && (start > end || end as usize > self.replacement_bytes.len()) {
+                return malformed("invalid replacement bounds");
+            }
+        }
+        // Each word belongs to exactly one state
+        let mut word_used = vec![false; self.replacement_bounds.len()];
+        for (index, state) in self.states.iter().enumerate() {
+            // Suffix links must always lead to a shallower state (except for root), otherwise following them might never end
+            if !is_state(state.suffix_link) || (index != 0 && self.states[state.suffix_link.index()].depth >= state.depth) {
+                return malformed("invalid suffix link");
+            }
+            // Output links lead to a shallower word
+            if state.output_link != StateId::FAIL && !(
+                is_state(state.output_link)
+                && self.states[state.output_link.index()].depth < state.depth
+                && self.states[state.output_link.index()].word != AcAutomaton::NOT_WORD
+            ) {
+                return malformed("invalid output link");
+            }
+            if state.word != AcAutomaton::NOT_WORD {
+                match word_used.get_mut(state.word as usize) {
+                    Some(used) if !*used => *used = true,
+                    _ => return malformed("invalid word"),
+                }
+            }
+            let row_start = state.row_start as usize;
+            let row_valid = if state.row_len == AcAutomaton::DENSE_ROW {
+                row_start.checked_add(alphabet_len).is_some_and(|row_end| row_end <= self.dense.len())
+            } else if state.row_len == AcAutomaton::DOUBLE_ARRAY_ROW {
+                row_start.checked_add(alphabet_len).is_some_and(|row_end| row_end <= self.check.len())
+            } else {
+                row_start.checked_add(state.row_len as usize).is_some_and(|row_end| row_end <= self.sparse_classes.len())
+            };
+            if !row_valid || (index == 0 && state.row_len != AcAutomaton::DENSE_ROW) {
+                return malformed("invalid transitions row");
+            }
+            // A transition goes exactly one byte deeper, and a DFA transition at most one byte deeper : the depth of a state never
+            // exceeds the amount of bytes read since root, which the streams rely on to hold back the last depth bytes
+            let depth = state.depth as u64;
+            let is_child = |next: &StateId| is_state(*next) && self.states[next.index()].depth as u64 == depth + 1;
+            let children_valid = if state.row_len == AcAutomaton::DENSE_ROW {
+                self.dense[row_start..row_start + alphabet_len].iter().filter(|next| **next != StateId::FAIL).all(is_child)
+            } else if state.row_len == AcAutomaton::DOUBLE_ARRAY_ROW {
+                (row_start..row_start + alphabet_len).filter(|position| self.check[*position].index() == index).all(|position| is_child(&self.dense[position]))
+            } else {
+                self.sparse_next[row_start..row_start + state.row_len as usize].iter().all(is_child)
+            };
+            if !children_valid {
+                return malformed("invalid transition depth");
+            }
+            if !self.dfa.is_empty() && !self.dfa[index * alphabet_len..(index + 1) * alphabet_len].iter().all(|next| self.states[next.index()].depth as u64 <= depth + 1) {
+                return malformed("invalid dfa table");
+            }
+        }
+        if !word_used.iter().all(|used| *used) {
+            return malformed("invalid word");
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u32s<'a>(bytes: &'a [u8], range: &Range<usize>) -> impl Iterator<Item = u32> + 'a {
+    bytes[range.clone()].chunks_exact(4).map(|value| u32::from_le_bytes(value.try_into().unwrap()))
+}
+
+/// CRC-32 (IEEE) lookup table, computed at compile time
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use alloc::vec::Vec;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::{Error, SeqAccess, Visitor}};
+    use crate::AhoCorasick;
+
+    /// Serialized as a byte string holding the binary format, so that it benefits from the same version and checksum checks
+    impl Serialize for AhoCorasick {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for AhoCorasick {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_bytes(AhoCorasickVisitor)
+        }
+    }
+
+    struct AhoCorasickVisitor;
+
+    impl<'de> Visitor<'de> for AhoCorasickVisitor {
+        type Value = AhoCorasick;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("a serialized aho-corasick-async automaton")
+        }
+
+        fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+            AhoCorasick::from_bytes(bytes).map_err(E::custom)
+        }
+
+        // Formats without a native byte string representation (e.g. JSON) give a sequence of bytes
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes: Vec<u8> = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            self.visit_bytes(&bytes)
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use core::mem::size_of;
+use crate::{AhoCorasick, automaton::{AcAutomaton, StateId}, prefilter::Prefilter, table::{Backing, Pod, Table}};
+
+/// Size of a compiled automaton, e.g. to budget the memory of each dictionary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AutomatonStats {
+    /// States of the automaton, root included
+    pub states: usize,
+    /// Words of the dictionary, protected words included
+    pub patterns: usize,
+    /// Depth of the deepest state, i.e. length of the longest word. The bytes held back by a stream as a potential match are always fewer
+    pub max_depth: usize,
+    /// Trie transitions, one per byte leading from a state to another (the suffix links are not counted)
+    pub transitions: usize,
+    /// Bytes of all the replacements
+    pub replacement_bytes: usize,
+    /// Estimate of the heap memory owned by the automaton. The tables used in place (from_shared_bytes, static_replacer!) are not counted,
+    /// as their memory belongs to the bytes given. Each stream additionally owns its lazy DFA cache, if enabled, and its potential match
+    pub heap_bytes: usize,
+}
+
+impl AcAutomaton {
+    /// Amounts of states, words, transitions and bytes of the automaton. Walks all the states, so it is meant to be called once per dictionary
+    pub fn stats(&self) -> AutomatonStats {
+        AutomatonStats {
+            states: self.states.len(),
+            patterns: self.replacement_bounds.len(),
+            max_depth: self.states.iter().map(|state| state.depth as usize).max().unwrap_or(0),
+            transitions: (0..self.states.len()).map(|index| self.transitions(StateId(index as u32)).len()).sum(),
+            replacement_bytes: self.replacement_bytes.len(),
+            heap_bytes: size_of::<Self>()
+                + self.states.heap_bytes()
+                + self.dense.heap_bytes()
+                + self.check.heap_bytes()
+                + self.sparse_classes.heap_bytes()
+                + self.sparse_next.heap_bytes()
+                + self.replacement_bounds.heap_bytes()
+                + self.replacement_bytes.heap_bytes()
+                + self.dfa.heap_bytes()
+                + self.prefilter.heap_bytes(),
+        }
+    }
+}
+
+impl AhoCorasick {
+    /// Amounts of states, words, transitions and bytes of the automaton, and an estimate of its memory. See AutomatonStats
+    pub fn stats(&self) -> AutomatonStats {
+        self.automaton.stats()
+    }
+}
+
+impl<T: Pod> Table<T> {
+    /// Memory allocated for the table : only an owned table has its own allocation
+    fn heap_bytes(&self) -> usize {
+        match self.backing() {
+            Backing::Owned(values) => values.capacity() * size_of::<T>(),
+            Backing::Shared(_) | Backing::Static => 0,
+        }
+    }
+}
+
+impl Prefilter {
+    fn heap_bytes(&self) -> usize {
+        match self {
+            Self::ByteSet(set) => size_of_val(&**set),
+            #[cfg(all(target_arch = "x86_64", feature = "std"))]
+            Self::Teddy(teddy) => size_of_val(&**teddy),
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{collections::VecDeque, hash::Hash, pin::Pin, sync::Arc, task::{Context, Poll}};
+use futures::Stream;
+use pin_project_lite::pin_project;
+use crate::{TokenAhoCorasick, token::TokenAutomaton};
+
+// Wrapper over a Stream of tokens. Polling from AhoCorasickStream gives the tokens with the replacements performed.
+// This is the token counterpart of AhoCorasickAsyncReader : the same replacement semantics, one token at a time instead of bytes
+pin_project! {
+    pub struct AhoCorasickStream<S: Stream> {
+        #[pin]
+        source: S,
+        automaton: Arc<TokenAutomaton<S::Item>>,
+        state: u32, // Per-stream state over the shared automaton
+        potential_buffer: VecDeque<S::Item>, // Tokens holding the start of a potential match
+        pending: VecDeque<S::Item>, // Tokens ready to be given
+        ended: bool, // The source has ended : the potential buffer was moved to pending
+    }
+}
+
+impl<S> AhoCorasickStream<S>
+where
+    S: Stream,
+    S::Item: Eq + Hash + Clone
+{
+    pub fn new(ac: TokenAhoCorasick<S::Item>, source: S) -> Self {
+        AhoCorasickStream {
+            source,
+            automaton: ac.automaton,
+            state: TokenAutomaton::<S::Item>::ROOT,
+            potential_buffer: VecDeque::new(),
+            pending: VecDeque::new(),
+            ended: false,
+        }
+    }
+}
+
+impl<S> Stream for AhoCorasickStream<S>
+where
+    S: Stream,
+    S::Item: Eq + Hash + Clone
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(token) = this.pending.pop_front() {
+                return Poll::Ready(Some(token));
+            }
+            if *this.ended {
+                return Poll::Ready(None);
+            }
+            match this.source.as_mut().poll_next(cx) {
+                Poll::Ready(Some(token)) => {
+                    // Back to root for a token in no word, as no word goes through it
+                    *this.state = this.automaton.next_state_for(*this.state, &token);
+                    this.potential_buffer.push_back(token);
+                    // Either we followed a potential word, or we jumped to a different branch following the suffix link (possibly root)
+                    // The potential buffer keeps as many last tokens as matched by the new state : the ones before it are given as is
+                    let current_state_len = this.automaton.depth(*this.state);
+                    while this.potential_buffer.len() > current_state_len {
+                        this.pending.push_back(this.potential_buffer.pop_front().unwrap());
+                    }
+                    if *this.state != TokenAutomaton::<S::Item>::ROOT && this.automaton.is_word(*this.state) {
+                        // Minimal size word detected => replacement, or protected word given as is. The state is reset in both cases
+                        match this.automaton.replacement(*this.state) {
+                            Some(replacement) => {
+                                this.potential_buffer.clear();
+                                this.pending.extend(replacement.iter().cloned());
+                            },
+                            None => this.pending.extend(this.potential_buffer.drain(..)),
+                        }
+                        *this.state = TokenAutomaton::<S::Item>::ROOT;
+                    }
+                },
+                Poll::Ready(None) => {
+                    // End reached - discard potential buffer
+                    this.pending.extend(this.potential_buffer.drain(..));
+                    *this.ended = true;
+                },
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
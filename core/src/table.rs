@@ -0,0 +1,121 @@
+use core::{fmt::Debug, ops::Deref};
+use alloc::{sync::Arc, vec::Vec};
+use crate::automaton::{State, StateId};
+
+/// Read-only bytes holding a serialized automaton, used in place (e.g. a memory-mapped file)
+pub(crate) type SharedBytes = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+/// One table of the automaton : a read-only slice, resolved once when the table is made, so that reading it is a plain slice access.
+/// The memory is kept alive by the backing, and never moves nor changes while the table exists
+pub(crate) struct Table<T: Pod> {
+    ptr: *const T,
+    len: usize,
+    backing: Backing<T>,
+}
+
+/// Memory of a table : either built in memory, or read in place from serialized bytes
+pub(crate) enum Backing<T: Pod> {
+    Owned(Vec<T>), // Never modified once in the table, so its buffer stays in place
+    Shared(#[allow(dead_code)] SharedBytes), // Only held to keep the bytes alive
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : static_replacer!
+    Static, // Embedded in the binary at compile time (see static_replacer!)
+}
+
+// SAFETY : the table only gives shared access to Pod values, in memory owned by the backing (or static), which is Send + Sync itself
+unsafe impl<T: Pod> Send for Table<T> {}
+unsafe impl<T: Pod> Sync for Table<T> {}
+
+/// Types which can be read directly from serialized bytes : plain little-endian u32 (or u8) fields, any bit pattern being a valid value
+/// # Safety
+/// The type must be made of u8 or u32 fields only, without any padding
+pub(crate) unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for [u32; 2] {}
+unsafe impl Pod for StateId {}
+unsafe impl Pod for State {}
+
+impl<T: Pod> Table<T> {
+    /// Checks that a slice of the shared bytes can be viewed as a slice of T. The bytes are resolved once by the caller : the source
+    /// must keep giving the same bytes (as a Vec, a boxed slice or a memory map do), which the Arc keeps alive for as long as the table
+    pub(crate) fn shared(bytes: &SharedBytes, slice: &[u8]) -> Option<Self> {
+        if !Self::is_viewable(slice) {
+            return None;
+        }
+        Some(Self {
+            ptr: slice.as_ptr() as *const T,
+            len: slice.len() / core::mem::size_of::<T>(),
+            backing: Backing::Shared(Arc::clone(bytes)),
+        })
+    }
+
+    /// Views static bytes as a slice of T, once and for all. None if they are not aligned for T
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : static_replacer!
+    pub(crate) fn from_static(bytes: &'static [u8]) -> Option<Self> {
+        if !Self::is_viewable(bytes) {
+            return None;
+        }
+        Some(Self {
+            ptr: bytes.as_ptr() as *const T,
+            len: bytes.len() / core::mem::size_of::<T>(),
+            backing: Backing::Static,
+        })
+    }
+
+    pub(crate) fn backing(&self) -> &Backing<T> {
+        &self.backing
+    }
+
+    #[inline(always)]
+    fn is_viewable(slice: &[u8]) -> bool {
+        (slice.as_ptr() as usize).is_multiple_of(core::mem::align_of::<T>()) && slice.len().is_multiple_of(core::mem::size_of::<T>())
+    }
+}
+
+impl<T: Pod> Deref for Table<T> {
+    type Target = [T];
+
+    #[inline(always)]
+    fn deref(&self) -> &[T] {
+        // SAFETY : T is Pod (any bit pattern is valid), and the pointer was checked to be aligned, with len values within the memory
+        // kept alive by the backing
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T: Pod> From<Vec<T>> for Table<T> {
+    /// The spare capacity is released, as the table is never modified
+    fn from(mut values: Vec<T>) -> Self {
+        values.shrink_to_fit();
+        Self {
+            ptr: values.as_ptr(),
+            len: values.len(),
+            backing: Backing::Owned(values),
+        }
+    }
+}
+
+impl<T: Pod> FromIterator<T> for Table<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<T>>().into()
+    }
+}
+
+impl<T: Pod> Debug for Table<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.backing {
+            Backing::Owned(_) => write!(f, "Owned({} entries)", self.len),
+            Backing::Shared(_) => write!(f, "Shared({} entries)", self.len),
+            Backing::Static => write!(f, "Static({} entries)", self.len),
+        }
+    }
+}
+
+/// Reserves room for more values, growing the capacity by a quarter instead of doubling it : the arrays of a large build then never
+/// hold much more memory than they use, while the amortized cost of a push stays constant
+pub(crate) fn reserve_compact<T>(values: &mut Vec<T>, additional: usize) {
+    if values.capacity() - values.len() < additional {
+        values.reserve_exact(additional.max(values.capacity() / 4).max(16));
+    }
+}
@@ -0,0 +1,131 @@
+use core::arch::x86_64::*;
+
+/// Packed substring search in the style of Teddy, for dictionaries having few distinct prefixes of 2 or 3 bytes.
+/// The prefixes are spread over 8 buckets. For each byte of the prefixes, two 16-entry tables give the buckets having a prefix
+/// with that low (resp. high) nibble at that position : a SIMD shuffle looks them up for 16 or 32 input positions at once.
+/// A position is a candidate when some bucket matches all the bytes of the window starting there. Candidates are a superset of the
+/// positions where a prefix actually starts (nibbles and buckets are shared), which is all the streams need : they walk the automaton from there
+#[derive(Debug, Clone)]
+pub(crate) struct Teddy {
+    len: usize, // Length of the prefixes, 2 or 3
+    masks: [[u8; 32]; 3], // For each byte of the prefixes : buckets by low nibble, then buckets by high nibble
+    avx2: bool,
+}
+
+impl Teddy {
+    /// Above this amount of prefixes, the buckets get too crowded, and most positions become candidates
+    pub(crate) const MAX_PREFIXES: usize = 64;
+    const BUCKETS: usize = 8;
+
+    /// Returns None if the CPU has no SSSE3 support, or if the prefixes are not suited (too many, or not 2 or 3 bytes long)
+    pub(crate) fn new(prefixes: &[Vec<u8>]) -> Option<Self> {
+        let len = prefixes.first()?.len();
+        if !(2..=3).contains(&len) || prefixes.len() > Self::MAX_PREFIXES || prefixes.iter().any(|prefix| prefix.len() != len) {
+            return None;
+        }
+        if !is_x86_feature_detected!("ssse3") {
+            return None;
+        }
+        let mut masks = [[0u8; 32]; 3];
+        // The prefixes are sorted : consecutive ones, sharing the same bucket, tend to have the same first bytes
+        let per_bucket = prefixes.len().div_ceil(Self::BUCKETS);
+        for (index, prefix) in prefixes.iter().enumerate() {
+            let bucket = 1u8 << (index / per_bucket);
+            for (position, byte) in prefix.iter().enumerate() {
+                masks[position][(*byte & 0x0F) as usize] |= bucket;
+                masks[position][16 + (*byte >> 4) as usize] |= bucket;
+            }
+        }
+        Some(Self {
+            len,
+            masks,
+            avx2: is_x86_feature_detected!("avx2"),
+        })
+    }
+
+    /// Position of the first candidate in the input, or the input length if there is none
+    #[inline]
+    pub(crate) fn find(&self, input: &[u8]) -> usize {
+        // SAFETY : the CPU features were detected when building
+        let position = unsafe {
+            if self.avx2 {
+                self.find_avx2(input)
+            } else {
+                self.find_ssse3(input)
+            }
+        };
+        match position {
+            Ok(position) => position,
+            // The last positions do not fill a whole vector : they are checked one by one
+            Err(tail) => (tail..input.len()).find(|position| self.is_candidate(input, *position)).unwrap_or(input.len())
+        }
+    }
+
+    /// Position of the first candidate, or Err with the position where the scalar search must continue
+    #[target_feature(enable = "ssse3")]
+    unsafe fn find_ssse3(&self, input: &[u8]) -> Result<usize, usize> {
+        let nibble = _mm_set1_epi8(0x0F);
+        let tables: [[__m128i; 2]; 3] = core::array::from_fn(|index| [
+            _mm_loadu_si128(self.masks[index].as_ptr() as *const __m128i),
+            _mm_loadu_si128(self.masks[index][16..].as_ptr() as *const __m128i),
+        ]);
+        let mut position = 0;
+        while position + 16 + self.len - 1 <= input.len() {
+            let mut buckets = _mm_set1_epi8(-1);
+            for (offset, [low, high]) in tables[..self.len].iter().enumerate() {
+                // SAFETY : the 16 bytes read are within the input, as checked by the loop condition
+                let chunk = _mm_loadu_si128(input.as_ptr().add(position + offset) as *const __m128i);
+                let low_buckets = _mm_shuffle_epi8(*low, _mm_and_si128(chunk, nibble));
+                let high_buckets = _mm_shuffle_epi8(*high, _mm_and_si128(_mm_srli_epi16(chunk, 4), nibble));
+                buckets = _mm_and_si128(buckets, _mm_and_si128(low_buckets, high_buckets));
+            }
+            let candidates = !(_mm_movemask_epi8(_mm_cmpeq_epi8(buckets, _mm_setzero_si128())) as u32) & 0xFFFF;
+            if candidates != 0 {
+                return Ok(position + candidates.trailing_zeros() as usize);
+            }
+            position += 16;
+        }
+        Err(position)
+    }
+
+    /// Same as find_ssse3, 32 positions at once. The shuffles work on each 16-byte lane separately, so the tables are duplicated in both lanes
+    #[target_feature(enable = "avx2")]
+    unsafe fn find_avx2(&self, input: &[u8]) -> Result<usize, usize> {
+        let nibble = _mm256_set1_epi8(0x0F);
+        let tables: [[__m256i; 2]; 3] = core::array::from_fn(|index| [
+            _mm256_broadcastsi128_si256(_mm_loadu_si128(self.masks[index].as_ptr() as *const __m128i)),
+            _mm256_broadcastsi128_si256(_mm_loadu_si128(self.masks[index][16..].as_ptr() as *const __m128i)),
+        ]);
+        let mut position = 0;
+        while position + 32 + self.len - 1 <= input.len() {
+            let mut buckets = _mm256_set1_epi8(-1);
+            for (offset, [low, high]) in tables[..self.len].iter().enumerate() {
+                // SAFETY : the 32 bytes read are within the input, as checked by the loop condition
+                let chunk = _mm256_loadu_si256(input.as_ptr().add(position + offset) as *const __m256i);
+                let low_buckets = _mm256_shuffle_epi8(*low, _mm256_and_si256(chunk, nibble));
+                let high_buckets = _mm256_shuffle_epi8(*high, _mm256_and_si256(_mm256_srli_epi16(chunk, 4), nibble));
+                buckets = _mm256_and_si256(buckets, _mm256_and_si256(low_buckets, high_buckets));
+            }
+            let candidates = !(_mm256_movemask_epi8(_mm256_cmpeq_epi8(buckets, _mm256_setzero_si256())) as u32);
+            if candidates != 0 {
+                return Ok(position + candidates.trailing_zeros() as usize);
+            }
+            position += 32;
+        }
+        // The remaining full 16-byte vectors are still worth a SIMD search
+        self.find_ssse3(&input[position..]).map(|found| position + found).map_err(|tail| position + tail)
+    }
+
+    /// Scalar check of a single position, with the same tables. Bytes past the end of the input are unknown, so they match any bucket
+    #[inline]
+    fn is_candidate(&self, input: &[u8], position: usize) -> bool {
+        let mut buckets = u8::MAX;
+        for (offset, masks) in self.masks[..self.len].iter().enumerate() {
+            let Some(byte) = input.get(position + offset) else {
+                break;
+            };
+            buckets &= masks[(*byte & 0x0F) as usize] & masks[16 + (*byte >> 4) as usize];
+        }
+        buckets != 0
+    }
+}
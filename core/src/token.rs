@@ -0,0 +1,169 @@
+use std::{collections::{HashMap, VecDeque}, hash::Hash, sync::Arc};
+use futures::Stream;
+use crate::AhoCorasickStream;
+
+/// Automaton matching sequences of tokens of any type (word IDs from a tokenizer, events from a protocol decoder, ...), with the same
+/// replacement semantics as AhoCorasick over bytes. Like AhoCorasick, it is immutable and cheap to clone
+///
+/// The tokens of the words are interned as symbols, and the automaton goes from state to state on them, whatever their amount.
+/// AhoCorasick is the specialization for bytes : its tables are indexed by the bytes themselves, which allows the DFA, the prefilters
+/// and the other options of the builder
+#[derive(Debug)]
+pub struct TokenAhoCorasick<T> {
+    pub(crate) automaton: Arc<TokenAutomaton<T>>,
+}
+
+impl<T> Clone for TokenAhoCorasick<T> {
+    fn clone(&self) -> Self {
+        Self {
+            automaton: Arc::clone(&self.automaton),
+        }
+    }
+}
+
+/// Trie of the words over the symbols of their tokens, with the suffix links. Tokens which are in no word have no symbol : the
+/// automaton goes back to root on them, as no word goes through them
+#[derive(Debug)]
+pub(crate) struct TokenAutomaton<T> {
+    symbols: HashMap<T, u32>, // Symbol of each token of the words
+    states: Vec<TokenState>,
+    transitions: Vec<(u32, u32)>, // Symbol and child of the transitions of all states, sorted by symbol in the row of each state
+    replacement_tokens: Vec<T>, // Tokens of all replacements, referenced by the states
+}
+
+#[derive(Debug, Clone)]
+struct TokenState {
+    row: [u32; 2], // Start and end of the transitions of the state
+    depth: u32, // Amount of tokens leading from root to the state
+    suffix_link: u32,
+    word: bool,
+    replacement: Option<[u32; 2]>, // Start and end in replacement_tokens. None for protected words
+}
+
+impl<T: Eq + Hash + Clone> TokenAutomaton<T> {
+    pub(crate) const ROOT: u32 = 0;
+
+    fn new<I>(replacements: I) -> Self
+    where
+        I: IntoIterator<Item = (Vec<T>, Option<Vec<T>>)>
+    {
+        let mut symbols = HashMap::new();
+        let mut states = vec![TokenState { row: [0, 0], depth: 0, suffix_link: Self::ROOT, word: false, replacement: None }];
+        let mut children: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut replacement_tokens = Vec::new();
+        for (word, replacement) in replacements {
+            let mut state = Self::ROOT;
+            for token in word {
+                let next_symbol = u32::try_from(symbols.len()).expect("More distinct tokens than symbols");
+                let symbol = *symbols.entry(token).or_insert(next_symbol);
+                state = *children.entry((state, symbol)).or_insert_with(|| {
+                    let depth = states[state as usize].depth + 1;
+                    states.push(TokenState { row: [0, 0], depth, suffix_link: Self::ROOT, word: false, replacement: None });
+                    states.len() as u32 - 1
+                });
+            }
+            // If the word was already present, its replacement is overwritten, as with AhoCorasickBuilder::build
+            let state = &mut states[state as usize];
+            state.word = true;
+            state.replacement = replacement.map(|replacement| {
+                let start = replacement_tokens.len() as u32;
+                replacement_tokens.extend(replacement);
+                [start, replacement_tokens.len() as u32]
+            });
+        }
+
+        // Rows of the states, in the order of the states
+        let mut transitions: Vec<(u32, u32, u32)> = children.into_iter().map(|((state, symbol), child)| (state, symbol, child)).collect();
+        transitions.sort_unstable();
+        let mut start = 0;
+        for (index, state) in states.iter_mut().enumerate() {
+            let len = transitions[start..].iter().take_while(|(parent, _, _)| *parent as usize == index).count();
+            state.row = [start as u32, (start + len) as u32];
+            start += len;
+        }
+        let mut automaton = Self {
+            symbols,
+            states,
+            transitions: transitions.into_iter().map(|(_, symbol, child)| (symbol, child)).collect(),
+            replacement_tokens,
+        };
+
+        // Suffix links, breadth first : the suffix links of the shallower states are known when the ones of their children are calculated
+        let mut queue: VecDeque<u32> = VecDeque::from([Self::ROOT]);
+        while let Some(state) = queue.pop_front() {
+            let [start, end] = automaton.states[state as usize].row;
+            for index in start..end {
+                let (symbol, child) = automaton.transitions[index as usize];
+                automaton.states[child as usize].suffix_link = if state == Self::ROOT {
+                    Self::ROOT
+                } else {
+                    automaton.next_state(automaton.states[state as usize].suffix_link, symbol)
+                };
+                queue.push_back(child);
+            }
+        }
+        automaton
+    }
+
+    /// Child of the state for the symbol, without following the suffix links
+    #[inline]
+    fn child(&self, state: u32, symbol: u32) -> Option<u32> {
+        let [start, end] = self.states[state as usize].row;
+        let row = &self.transitions[start as usize..end as usize];
+        row.binary_search_by_key(&symbol, |(symbol, _)| *symbol).ok().map(|index| row[index].1)
+    }
+
+    #[inline]
+    fn next_state(&self, mut state: u32, symbol: u32) -> u32 {
+        loop {
+            if let Some(child) = self.child(state, symbol) {
+                return child;
+            }
+            if state == Self::ROOT {
+                return Self::ROOT;
+            }
+            state = self.states[state as usize].suffix_link;
+        }
+    }
+
+    /// State reached from the state with the token. Root if the token is in no word
+    #[inline]
+    pub(crate) fn next_state_for(&self, state: u32, token: &T) -> u32 {
+        match self.symbols.get(token) {
+            Some(symbol) => self.next_state(state, *symbol),
+            None => Self::ROOT,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_word(&self, state: u32) -> bool {
+        self.states[state as usize].word
+    }
+
+    /// Amount of last tokens matched by the state
+    #[inline]
+    pub(crate) fn depth(&self, state: u32) -> usize {
+        self.states[state as usize].depth as usize
+    }
+
+    /// Replacement of a word state. None for a protected word
+    #[inline]
+    pub(crate) fn replacement(&self, state: u32) -> Option<&[T]> {
+        let [start, end] = self.states[state as usize].replacement?;
+        Some(&self.replacement_tokens[start as usize..end as usize])
+    }
+}
+
+impl<T: Eq + Hash + Clone> TokenAhoCorasick<T> {
+    /// Same as AhoCorasick::new, with words and replacements made of tokens
+    pub fn new(replacements: Vec<(Vec<T>, Option<Vec<T>>)>) -> Self {
+        Self {
+            automaton: Arc::new(TokenAutomaton::new(replacements)),
+        }
+    }
+
+    /// Wraps a stream of tokens : polling the returned stream gives the tokens with the replacements performed
+    pub fn into_stream<S: Stream<Item = T>>(self, source: S) -> AhoCorasickStream<S> {
+        AhoCorasickStream::new(self, source)
+    }
+}
@@ -0,0 +1,127 @@
+use core::fmt::Display;
+use alloc::{string::ToString, vec::Vec};
+use crate::{AhoCorasick, automaton::{AcAutomaton, StateId}};
+
+/// Step-by-step replay of the replacements over an input, explaining why it came out the way it did. Printed as a table, one row per byte
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+    /// Replaced input, the same as given by the streams
+    pub output: Vec<u8>,
+    /// Bytes of the potential match still held back at the end of the input, written as is
+    pub flushed_at_end: usize,
+}
+
+/// What happened at one byte of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Position of the byte in the input
+    pub position: usize,
+    pub byte: u8,
+    /// State before the byte (root after a match)
+    pub from: StateId,
+    /// State reached with the byte
+    pub to: StateId,
+    /// Suffix links followed before finding a transition for the byte : 0 when the trie transition of the state was taken directly
+    pub suffix_links: usize,
+    /// Depth of the state reached : amount of last bytes held back as the beginning of a word
+    pub depth: usize,
+    /// Bytes of the potential match written as is, as they can not be part of a match anymore
+    pub flushed: usize,
+    pub outcome: TraceOutcome,
+}
+
+/// Whether a word was found at a step. In both cases, the state is reset to root after the step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceOutcome {
+    /// No word ends here
+    Pending,
+    /// The depth last bytes were replaced by the replacement
+    Replaced(Vec<u8>),
+    /// The depth last bytes are a word without replacement : they were written as is
+    Protected,
+}
+
+impl AcAutomaton {
+    /// Replays the replacements over the input, recording each transition taken. The results are the same as the streams' with any
+    /// configuration : the trie transitions and suffix links are followed one by one, even in DFA mode, so that they can be reported
+    pub fn trace(&self, input: &[u8]) -> Trace {
+        let mut steps = Vec::with_capacity(input.len());
+        let mut output = Vec::new();
+        let mut state = self.start_state();
+        let mut potential_start = 0usize; // The depth last bytes are held back
+        for (position, byte) in input.iter().enumerate() {
+            let from = state;
+            let class = self.byte_classes.get(*byte);
+            let mut suffix_links = 0;
+            state = loop {
+                if let Some(next) = self.goto(state, class) {
+                    break next;
+                }
+                if state == StateId::ROOT {
+                    break state;
+                }
+                state = self.states[state.index()].suffix_link;
+                suffix_links += 1;
+            };
+            let depth = self.depth(state);
+            // The depth grows by one byte at most per transition (as checked when loading the tables), so the held back bytes always cover it
+            let match_start = position + 1 - depth;
+            let flushed = match_start - potential_start;
+            output.extend_from_slice(&input[potential_start..match_start]);
+            potential_start = match_start;
+            let outcome = if self.is_match(state) {
+                let outcome = match self.replacement(state) {
+                    Some(replacement) => {
+                        output.extend_from_slice(replacement);
+                        TraceOutcome::Replaced(replacement.to_vec())
+                    },
+                    None => {
+                        output.extend_from_slice(&input[match_start..=position]);
+                        TraceOutcome::Protected
+                    }
+                };
+                potential_start = position + 1;
+                outcome
+            } else {
+                TraceOutcome::Pending
+            };
+            steps.push(TraceStep { position, byte: *byte, from, to: state, suffix_links, depth, flushed, outcome });
+            if self.is_match(state) {
+                state = self.start_state();
+            }
+        }
+        output.extend_from_slice(&input[potential_start..]);
+        Trace {
+            steps,
+            output,
+            flushed_at_end: input.len() - potential_start,
+        }
+    }
+}
+
+impl AhoCorasick {
+    /// Step-by-step replay of the replacements over the input, for debugging a rule set. See AcAutomaton::trace
+    pub fn trace(&self, input: &[u8]) -> Trace {
+        self.automaton.trace(input)
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{:>8} {:<6} {:>6} {:>6} {:>6} {:>6} {:>8}  outcome", "position", "byte", "from", "to", "links", "depth", "flushed")?;
+        for step in self.steps.iter() {
+            write!(
+                f, "{:>8} {:<6} {:>6} {:>6} {:>6} {:>6} {:>8}",
+                step.position, core::ascii::escape_default(step.byte).to_string(), step.from.0, step.to.0, step.suffix_links, step.depth, step.flushed
+            )?;
+            match &step.outcome {
+                TraceOutcome::Pending => writeln!(f)?,
+                TraceOutcome::Replaced(replacement) => writeln!(f, "  replaced by \"{}\", reset", replacement.escape_ascii())?,
+                TraceOutcome::Protected => writeln!(f, "  protected word, reset")?,
+            }
+        }
+        writeln!(f, "end : {} bytes flushed", self.flushed_at_end)?;
+        write!(f, "output : \"{}\"", self.output.escape_ascii())
+    }
+}
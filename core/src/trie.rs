@@ -0,0 +1,430 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use crate::{automaton::{AcAutomaton, State, StateId}, table::reserve_compact};
+
+/// Build-time trie, from which the contiguous AcAutomaton is compiled
+/// Nodes are stored in an arena and reference each other by their index. Node 0 is the root. Each node already is a State of the
+/// automaton, except that its row fields hold its first child and its next sibling : the children of a node are a list sorted by byte,
+/// threaded through the arena, so that no node owns an allocation. Once compiled, the arena becomes the state table in place
+#[derive(Debug, Clone)]
+pub(crate) struct Trie {
+    nodes: Vec<State>, // row_start : first child, row_len : next sibling. Links are StateId::FAIL until calculated
+    bytes: Vec<u8>, // Byte leading from the parent to each node. Unused for root
+    root_row: [u32; 256], // Child of root for each byte, as root is the most looked up node. NONE if there is no child
+    replacement_bounds: Vec<[u32; 2]>, // Start and end in replacement_bytes, for each word, as in the automaton
+    replacement_bytes: Vec<u8>, // Also holds the replacements overwritten or removed, until compact_words
+}
+
+impl Trie {
+    pub(crate) const ROOT: u32 = 0;
+    /// Missing child or sibling
+    const NONE: u32 = u32::MAX;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: vec![Self::node(0)],
+            bytes: vec![0],
+            root_row: [Self::NONE; 256],
+            replacement_bounds: Vec::new(),
+            replacement_bytes: Vec::new(),
+        }
+    }
+
+    /// Node without children, links nor word
+    fn node(depth: u32) -> State {
+        State {
+            depth,
+            suffix_link: StateId::FAIL,
+            output_link: StateId::FAIL,
+            word: AcAutomaton::NOT_WORD,
+            row_start: Self::NONE,
+            row_len: Self::NONE,
+        }
+    }
+
+    /// Amount of nodes in the arena, including the removed ones waiting to be reused
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Adds a word with its optional replacement. If the word was already present, its replacement is overwritten
+    /// The path holds the nodes of the previously added word (root first), and is updated with the nodes of this word : the prefix shared
+    /// with the previous word is not looked up again. Walks down one node per byte in a loop, so that the length of the word is not limited by the stack
+    pub(crate) fn add_word(&mut self, path: &mut Vec<u32>, previous: &[u8], word: &[u8], replacement: Option<Vec<u8>>) {
+        let common = previous.iter().zip(word.iter()).take_while(|(previous, val)| previous == val).count();
+        path.truncate(common + 1);
+        for val in word[common..].iter() {
+            let node = *path.last().expect("Logic error : root is always in the path");
+            let child = match self.child(node, *val) {
+                Some(child) => child,
+                None => {
+                    let child = self.push_node(self.depth(node) + 1);
+                    self.add_child(node, *val, child);
+                    child
+                }
+            };
+            path.push(child);
+        }
+        self.set_word(*path.last().expect("Logic error : root is always in the path"), replacement);
+    }
+
+    /// Appends a node of the given depth to the arena. It is not the child of any node yet
+    pub(crate) fn push_node(&mut self, depth: u32) -> u32 {
+        reserve_compact(&mut self.nodes, 1);
+        reserve_compact(&mut self.bytes, 1);
+        self.nodes.push(Self::node(depth));
+        self.bytes.push(0);
+        self.nodes.len() as u32 - 1
+    }
+
+    /// Clears a node removed from the trie, so that it can be reused with the given depth
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : AhoCorasickEditor
+    pub(crate) fn reset_node(&mut self, node: u32, depth: u32) {
+        self.nodes[node as usize] = Self::node(depth);
+    }
+
+    /// Makes the node a word, with its optional replacement. Returns true if it already was one : its replacement is then overwritten
+    pub(crate) fn set_word(&mut self, node: u32, replacement: Option<Vec<u8>>) -> bool {
+        let bounds = match replacement {
+            Some(replacement) => {
+                let start = self.replacement_bytes.len() as u32;
+                reserve_compact(&mut self.replacement_bytes, replacement.len());
+                self.replacement_bytes.extend_from_slice(&replacement);
+                [start, self.replacement_bytes.len() as u32]
+            },
+            None => AcAutomaton::NO_REPLACEMENT
+        };
+        let this = &mut self.nodes[node as usize];
+        if this.word != AcAutomaton::NOT_WORD {
+            self.replacement_bounds[this.word as usize] = bounds;
+            return true;
+        }
+        reserve_compact(&mut self.replacement_bounds, 1);
+        this.word = self.replacement_bounds.len() as u32;
+        self.replacement_bounds.push(bounds);
+        false
+    }
+
+    /// Makes the node not a word anymore. Returns false if it was not one
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : AhoCorasickEditor
+    pub(crate) fn unset_word(&mut self, node: u32) -> bool {
+        let this = &mut self.nodes[node as usize];
+        let was_word = this.word != AcAutomaton::NOT_WORD;
+        this.word = AcAutomaton::NOT_WORD;
+        was_word
+    }
+
+    #[inline]
+    pub(crate) fn is_word(&self, node: u32) -> bool {
+        self.nodes[node as usize].word != AcAutomaton::NOT_WORD
+    }
+
+    #[inline]
+    pub(crate) fn depth(&self, node: u32) -> u32 {
+        self.nodes[node as usize].depth
+    }
+
+    /// Suffix link of the node. None for root, and for the nodes not linked yet
+    #[inline]
+    pub(crate) fn suffix_link(&self, node: u32) -> Option<u32> {
+        let suffix_link = self.nodes[node as usize].suffix_link;
+        (suffix_link != StateId::FAIL).then_some(suffix_link.0)
+    }
+
+    #[inline]
+    pub(crate) fn output_link(&self, node: u32) -> Option<u32> {
+        let output_link = self.nodes[node as usize].output_link;
+        (output_link != StateId::FAIL).then_some(output_link.0)
+    }
+
+    pub(crate) fn set_suffix_link(&mut self, node: u32, suffix_link: u32) {
+        self.nodes[node as usize].suffix_link = StateId(suffix_link);
+    }
+
+    pub(crate) fn set_output_link(&mut self, node: u32, output_link: Option<u32>) {
+        self.nodes[node as usize].output_link = output_link.map_or(StateId::FAIL, StateId);
+    }
+
+    /// Links an existing node as the child of another one, keeping the children sorted by byte
+    pub(crate) fn add_child(&mut self, node: u32, byte: u8, child: u32) {
+        self.bytes[child as usize] = byte;
+        if node == Self::ROOT {
+            self.root_row[byte as usize] = child;
+        }
+        let mut previous = Self::NONE;
+        let mut next = self.nodes[node as usize].row_start;
+        while next != Self::NONE && self.bytes[next as usize] < byte {
+            previous = next;
+            next = self.nodes[next as usize].row_len;
+        }
+        self.nodes[child as usize].row_len = next;
+        match previous {
+            Self::NONE => self.nodes[node as usize].row_start = child,
+            previous => self.nodes[previous as usize].row_len = child,
+        }
+    }
+
+    /// Unlinks the child of the node following the given byte
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : AhoCorasickEditor
+    pub(crate) fn remove_child(&mut self, node: u32, byte: u8) {
+        let mut previous = Self::NONE;
+        let mut next = self.nodes[node as usize].row_start;
+        while next != Self::NONE && self.bytes[next as usize] != byte {
+            previous = next;
+            next = self.nodes[next as usize].row_len;
+        }
+        if next == Self::NONE {
+            return;
+        }
+        let after = self.nodes[next as usize].row_len;
+        match previous {
+            Self::NONE => self.nodes[node as usize].row_start = after,
+            previous => self.nodes[previous as usize].row_len = after,
+        }
+        if node == Self::ROOT {
+            self.root_row[byte as usize] = Self::NONE;
+        }
+    }
+
+    /// Direct child of the node following the given byte, if any
+    #[inline]
+    pub(crate) fn child(&self, node: u32, byte: u8) -> Option<u32> {
+        if node == Self::ROOT {
+            let child = self.root_row[byte as usize];
+            return (child != Self::NONE).then_some(child);
+        }
+        self.children(node).take_while(|(val, _)| *val <= byte).find(|(val, _)| *val == byte).map(|(_, child)| child)
+    }
+
+    /// Children of the node with their bytes, sorted by byte
+    pub(crate) fn children(&self, node: u32) -> impl Iterator<Item = (u8, u32)> + '_ {
+        let mut next = self.nodes[node as usize].row_start;
+        core::iter::from_fn(move || {
+            let child = next;
+            (child != Self::NONE).then(|| {
+                next = self.nodes[child as usize].row_len;
+                (self.bytes[child as usize], child)
+            })
+        })
+    }
+
+    #[cfg_attr(not(feature = "std"), allow(dead_code))] // Only used by the std parts : AhoCorasickEditor
+    pub(crate) fn has_children(&self, node: u32) -> bool {
+        self.nodes[node as usize].row_start != Self::NONE
+    }
+
+    /// Breadth-first calculating suffix links for each node
+    pub(crate) fn breadth_first_walk(&mut self) {
+        let mut walk = LinksWalk::new(self);
+        while walk.step(self).is_some() {}
+    }
+
+    /// Calculates the suffix and output links for all children of the given node. Assumes that all N-1 nodes' suffix links are already determined
+    pub(crate) fn calculate_children_links(&mut self, node: u32) {
+        let mut child = self.nodes[node as usize].row_start;
+        while child != Self::NONE {
+            let suffix_link = self.child_suffix_link(node, self.bytes[child as usize]);
+            let output_link = self.output_link_through(suffix_link);
+            self.set_suffix_link(child, suffix_link);
+            self.set_output_link(child, output_link);
+            child = self.nodes[child as usize].row_len;
+        }
+    }
+
+    /// Suffix link of the child of the node following the given byte. Assumes that the node's suffix link is already determined
+    pub(crate) fn child_suffix_link(&self, node: u32, val: u8) -> u32 {
+        if node == Self::ROOT {
+            // First level children suffix links are always pointing to root
+            return Self::ROOT;
+        }
+        // First iteration : ancestor is direct parent's suffix link. If not found, follow its suffix link to next ancestor, and repeat search
+        let mut ancestor = self.suffix_link(node).expect("Logic error : current node's suffix link must always exist");
+        loop {
+            match self.child(ancestor, val) {
+                Some(step_val) => return step_val,
+                None => match self.suffix_link(ancestor) {
+                    Some(prev_ancestor) => ancestor = prev_ancestor,
+                    None => return ancestor // Root is reached
+                }
+            }
+        }
+    }
+
+    /// Output link of a node having the given suffix link : either the suffix link itself if it is a word, or that suffix's output link
+    pub(crate) fn output_link_through(&self, suffix_link: u32) -> Option<u32> {
+        if self.is_word(suffix_link) { Some(suffix_link) } else { self.output_link(suffix_link) }
+    }
+
+    /// Renumbers the nodes in breadth-first order, children in byte order, and drops the nodes not reachable from root (the removed
+    /// ones). Root stays the first node. The nodes are moved in place, following the cycles of the permutation
+    pub(crate) fn renumber_breadth_first(&mut self) {
+        // No node is added anymore : the spare capacity is released before the renumbering tables are allocated
+        self.nodes.shrink_to_fit();
+        self.bytes.shrink_to_fit();
+        let mut order: Vec<u32> = Vec::with_capacity(self.nodes.len());
+        order.push(Self::ROOT);
+        let mut walked = 0;
+        while walked < order.len() {
+            let node = order[walked];
+            walked += 1;
+            order.extend(self.children(node).map(|(_, child)| child));
+        }
+        let reachable = order.len();
+        let mut ids: Vec<u32> = vec![Self::NONE; self.nodes.len()];
+        for (id, node) in order.into_iter().enumerate() {
+            ids[node as usize] = id as u32;
+        }
+        for (next_id, id) in (reachable as u32..).zip(ids.iter_mut().filter(|id| **id == Self::NONE)) {
+            *id = next_id;
+        }
+
+        let id = |node: u32| if node == Self::NONE { Self::NONE } else { ids[node as usize] };
+        for this in self.nodes.iter_mut() {
+            this.suffix_link = StateId(id(this.suffix_link.0));
+            this.output_link = StateId(id(this.output_link.0));
+            this.row_start = id(this.row_start);
+            this.row_len = id(this.row_len);
+        }
+        for child in self.root_row.iter_mut() {
+            *child = id(*child);
+        }
+        for index in 0..ids.len() {
+            while ids[index] as usize != index {
+                let target = ids[index] as usize;
+                self.nodes.swap(index, target);
+                self.bytes.swap(index, target);
+                ids.swap(index, target);
+            }
+        }
+        self.nodes.truncate(reachable);
+        self.bytes.truncate(reachable);
+    }
+
+    /// Numbers the words in depth-first order, children in byte order (the order of the sorted words), and drops the replacements
+    /// overwritten or removed. The replacements are only copied when they are not in this order already : the words were added
+    /// unsorted, with duplicates, or edited
+    pub(crate) fn compact_words(&mut self) {
+        let mut words = 0;
+        let mut end = 0;
+        let mut replacement_len = 0;
+        let mut in_order = true;
+        for node in self.depth_first() {
+            let word = self.nodes[node as usize].word;
+            if word == AcAutomaton::NOT_WORD {
+                continue;
+            }
+            in_order &= word as usize == words;
+            words += 1;
+            let bounds = self.replacement_bounds[word as usize];
+            if bounds != AcAutomaton::NO_REPLACEMENT {
+                in_order &= bounds[0] == end;
+                end = bounds[1];
+                replacement_len += (bounds[1] - bounds[0]) as usize;
+            }
+        }
+        if in_order && words == self.replacement_bounds.len() && end as usize == self.replacement_bytes.len() {
+            self.replacement_bounds.shrink_to_fit();
+            self.replacement_bytes.shrink_to_fit();
+            return;
+        }
+        let word_nodes: Vec<u32> = self.depth_first().filter(|node| self.is_word(*node)).collect();
+        let mut replacement_bounds: Vec<[u32; 2]> = Vec::with_capacity(words);
+        let mut replacement_bytes: Vec<u8> = Vec::with_capacity(replacement_len);
+        for node in word_nodes {
+            let this = &mut self.nodes[node as usize];
+            replacement_bounds.push(match self.replacement_bounds[this.word as usize] {
+                AcAutomaton::NO_REPLACEMENT => AcAutomaton::NO_REPLACEMENT,
+                [start, end] => {
+                    let start_copy = replacement_bytes.len() as u32;
+                    replacement_bytes.extend_from_slice(&self.replacement_bytes[start as usize..end as usize]);
+                    [start_copy, replacement_bytes.len() as u32]
+                }
+            });
+            this.word = replacement_bounds.len() as u32 - 1;
+        }
+        self.replacement_bounds = replacement_bounds;
+        self.replacement_bytes = replacement_bytes;
+    }
+
+    /// Nodes in depth-first order, children in byte order. The stack holds at most one pending sibling per level
+    fn depth_first(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut stack: Vec<u32> = vec![Self::ROOT];
+        core::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let this = &self.nodes[node as usize];
+            if this.row_len != Self::NONE {
+                stack.push(this.row_len);
+            }
+            if this.row_start != Self::NONE {
+                stack.push(this.row_start);
+            }
+            Some(node)
+        })
+    }
+
+    /// The arena, which becomes the state table once the rows are written in place of the children lists, and the replacements
+    pub(crate) fn into_tables(self) -> (Vec<State>, Vec<[u32; 2]>, Vec<u8>) {
+        (self.nodes, self.replacement_bounds, self.replacement_bytes)
+    }
+
+    /// Mutable access to the node, to write its transitions row once its children are not walked anymore
+    pub(crate) fn node_mut(&mut self, node: u32) -> &mut State {
+        &mut self.nodes[node as usize]
+    }
+}
+
+/// Adds the words one after the other, one word per step (see Trie::add_word). Used by the blocking and the async builds
+pub(crate) struct TrieInsertion {
+    pub(crate) trie: Trie,
+    path: Vec<u32>, // Nodes of the previous word, root first
+    previous: Vec<u8>,
+    pub(crate) words: usize, // Words inserted so far
+    pub(crate) depth: usize, // Length of the longest word inserted so far
+}
+
+impl TrieInsertion {
+    pub(crate) fn new() -> Self {
+        Self {
+            trie: Trie::new(),
+            path: vec![Trie::ROOT],
+            previous: Vec::new(),
+            words: 0,
+            depth: 0,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, word: Vec<u8>, replacement: Option<Vec<u8>>) {
+        self.depth = self.depth.max(word.len());
+        self.trie.add_word(&mut self.path, &self.previous, &word, replacement);
+        self.previous = word;
+        self.words += 1;
+    }
+}
+
+/// Breadth-first walk calculating the suffix and output links, one node per step : the links of the children of a node only depend
+/// on the links of the shallower nodes. Used by the blocking and the async builds
+pub(crate) struct LinksWalk {
+    to_walk: VecDeque<u32>,
+}
+
+impl LinksWalk {
+    /// Starts the walk with the children of root, whose suffix links are always pointing to root. Their output link is root itself
+    /// if the empty word is in the dictionary, as set by AhoCorasickEditor
+    pub(crate) fn new(trie: &mut Trie) -> Self {
+        let to_walk: VecDeque<u32> = trie.children(Trie::ROOT).map(|(_, child)| child).collect();
+        let output_link = trie.output_link_through(Trie::ROOT);
+        for root_child in to_walk.iter() {
+            trie.set_suffix_link(*root_child, Trie::ROOT);
+            trie.set_output_link(*root_child, output_link);
+        }
+        Self { to_walk }
+    }
+
+    /// Calculates the links of the children of the next node, and queues them. Returns the depth of the node, or None once all the
+    /// nodes are linked. The nodes come level after level, so the depths returned never decrease
+    pub(crate) fn step(&mut self, trie: &mut Trie) -> Option<u32> {
+        let node = self.to_walk.pop_front()?;
+        trie.calculate_children_links(node);
+        self.to_walk.extend(trie.children(node).map(|(_, child)| child));
+        Some(trie.depth(node))
+    }
+}
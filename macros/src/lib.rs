@@ -0,0 +1,64 @@
+use aho_corasick_async_core::AhoCorasick;
+use proc_macro::TokenStream;
+use proc_macro2::Literal;
+use quote::quote;
+use syn::{Error, Lit, Token, parse::{Parse, ParseStream}, parse_macro_input, punctuated::Punctuated};
+
+/// One `word => replacement` entry. The word and the replacement are string or byte string literals, and the replacement can be None
+struct Replacement {
+    word: Vec<u8>,
+    replacement: Option<Vec<u8>>,
+}
+
+impl Parse for Replacement {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let word = Self::bytes(&input.parse()?)?;
+        input.parse::<Token![=>]>()?;
+        let replacement = if input.peek(syn::Ident) {
+            let ident: syn::Ident = input.parse()?;
+            if ident != "None" {
+                return Err(Error::new(ident.span(), "expected a string literal, a byte string literal or None"));
+            }
+            None
+        } else {
+            Some(Self::bytes(&input.parse()?)?)
+        };
+        Ok(Self { word, replacement })
+    }
+}
+
+impl Replacement {
+    fn bytes(lit: &Lit) -> syn::Result<Vec<u8>> {
+        match lit {
+            Lit::Str(lit) => Ok(lit.value().into_bytes()),
+            Lit::ByteStr(lit) => Ok(lit.value()),
+            _ => Err(Error::new(lit.span(), "expected a string literal or a byte string literal")),
+        }
+    }
+}
+
+/// Builds the automaton at compile time, and embeds its tables in the binary as static data :
+/// ```ignore
+/// let ac: AhoCorasick = aho_corasick_async::static_replacer! {
+///     "he" => "she",
+///     "she" => None, // Protected word
+///     b"\xFF\xFE" => b"",
+/// };
+/// ```
+/// The value is an AhoCorasick, built with the default configuration. The macro is meant to be used through aho-corasick-async, with its macros feature. Its tables are used in place : nothing is parsed nor copied
+/// at runtime, they are only checked and a small header allocated once, when it is first used. Cloning it afterwards is as cheap as cloning any AhoCorasick
+#[proc_macro]
+pub fn static_replacer(input: TokenStream) -> TokenStream {
+    let replacements = parse_macro_input!(input with Punctuated::<Replacement, Token![,]>::parse_terminated);
+    let ac = AhoCorasick::builder().build_from_iter(replacements.into_iter().map(|entry| (entry.word, entry.replacement)));
+    let bytes = ac.to_bytes();
+    let len = bytes.len();
+    let bytes = Literal::byte_string(&bytes);
+    quote! {
+        {
+            static BYTES: ::aho_corasick_async::__private::Aligned<#len> = ::aho_corasick_async::__private::Aligned(*#bytes);
+            static AUTOMATON: ::aho_corasick_async::__private::OnceLock<::aho_corasick_async::AhoCorasick> = ::aho_corasick_async::__private::OnceLock::new();
+            ::aho_corasick_async::__private::static_automaton(&AUTOMATON, &BYTES.0)
+        }
+    }.into()
+}
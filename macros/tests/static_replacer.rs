@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use aho_corasick_async::{AhoCorasick, Replacer, static_replacer};
+
+/// Performs the replacements on the whole input at once
+fn replace_all(ac: &AhoCorasick, input: &[u8]) -> Vec<u8> {
+    let mut replacer = Replacer::new(ac.clone());
+    let mut output = Vec::new();
+    replacer.replace(input, &mut output);
+    replacer.finish(&mut output);
+    output
+}
+
+fn pronouns() -> AhoCorasick {
+    static_replacer! {
+        "he" => "she",
+        "she" => "he",
+        "his" => "her",
+        "her" => "his",
+        "shed" => None,
+        b"\xE3\x81\x82" => b"[a]",
+        "unused" => "",
+    }
+}
+
+#[test]
+fn test_static_replacer() {
+    let ac = pronouns();
+    // The tables are the ones embedded in the binary, used in place
+    assert!(format!("{:?}", ac.automaton).contains("Static("));
+    let expected = AhoCorasick::new(Vec::from([
+        ("he".as_bytes().to_vec(), Some("she".as_bytes().to_vec())),
+        ("she".as_bytes().to_vec(), Some("he".as_bytes().to_vec())),
+        ("his".as_bytes().to_vec(), Some("her".as_bytes().to_vec())),
+        ("her".as_bytes().to_vec(), Some("his".as_bytes().to_vec())),
+        ("shed".as_bytes().to_vec(), None),
+        ("あ".as_bytes().to_vec(), Some("[a]".as_bytes().to_vec())),
+        ("unused".as_bytes().to_vec(), Some("".as_bytes().to_vec())),
+    ]));
+    assert!(ac.to_bytes() == expected.to_bytes());
+    let input = "Now he is here, so is she. his hair is blond, her bag is big. あいう unused".as_bytes();
+    assert_eq!(replace_all(&ac, input), replace_all(&expected, input));
+    // Every expansion shares the same automaton, loaded once
+    assert!(Arc::ptr_eq(&ac.automaton, &pronouns().automaton));
+    assert!(static_replacer! {}.to_bytes() == AhoCorasick::new(Vec::new()).to_bytes());
+}
@@ -0,0 +1,87 @@
+use aho_corasick_async::{AhoCorasick, BuildCancelled, BuildProgress, CancellationToken, CompilePass};
+use futures::executor::block_on;
+use test_utils::replace_all;
+
+mod test_utils;
+
+/// Enough words for several slices of work, in no particular order
+fn replacements() -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    let mut seed = 7u32;
+    (0..20000).map(|index| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let word = format!("w{}{}", (seed >> 16) % 1000, "x".repeat(index % 5));
+        (word.into_bytes(), (index % 3 != 0).then(|| format!("<{}>", index).into_bytes()))
+    }).collect()
+}
+
+#[test]
+fn test_build_async() {
+    let replacements = replacements();
+    let builders = [
+        (AhoCorasick::builder(), vec![CompilePass::Rows]),
+        (AhoCorasick::builder().dfa(true).minimize(true).clone(), vec![CompilePass::Rows, CompilePass::Minimize, CompilePass::Dfa]),
+        (AhoCorasick::builder().minimize(true).double_array(true).clone(), vec![CompilePass::Rows, CompilePass::Minimize, CompilePass::DoubleArray]),
+    ];
+    for (builder, passes) in builders {
+        let mut steps = Vec::new();
+        let ac = block_on(builder.build_async(replacements.clone(), |step| steps.push(step), CancellationToken::new())).unwrap();
+        // Exactly the same automaton as the blocking build
+        assert!(ac.to_bytes() == builder.build(replacements.clone()).to_bytes());
+        assert_eq!(replace_all(&ac, b"w1 w12xx w999x"), replace_all(&builder.build(replacements.clone()), b"w1 w12xx w999x"));
+
+        let depth = replacements.iter().map(|(word, _)| word.len()).max().unwrap();
+        assert!(steps.contains(&BuildProgress::Inserting { words: 4096 }));
+        assert!(steps.contains(&BuildProgress::Inserting { words: replacements.len() }));
+        assert!(steps.contains(&BuildProgress::Linking { levels: 1, depth }));
+        assert!(steps.contains(&BuildProgress::Linking { levels: depth, depth }));
+        // The compilation is reported in slices too, only the enabled passes running
+        let compiled: Vec<(CompilePass, usize, usize)> = steps.iter().filter_map(|step| match step {
+            BuildProgress::Compiling { pass, states, total } => Some((*pass, *states, *total)),
+            _ => None,
+        }).collect();
+        let total = compiled[0].2;
+        assert!(compiled.contains(&(CompilePass::Rows, 4096, total)));
+        assert!(compiled.contains(&(CompilePass::Rows, total, total)));
+        assert_eq!(compiled.iter().map(|(pass, _, _)| *pass).collect::<std::collections::BTreeSet<_>>(), passes.into_iter().collect());
+        // The last pass runs over the states of the final automaton
+        let states = ac.stats().states;
+        assert_eq!(compiled.last().map(|(_, done, total)| (*done, *total)), Some((states, states)));
+        // Steps only move forward
+        let rank = |step: &BuildProgress| match step {
+            BuildProgress::Inserting { words } => (0, CompilePass::Rows, *words),
+            BuildProgress::Linking { levels, .. } => (1, CompilePass::Rows, *levels),
+            BuildProgress::Compiling { pass, states, .. } => (2, *pass, *states),
+        };
+        assert!(steps.windows(2).all(|pair| rank(&pair[0]) <= rank(&pair[1])));
+    }
+}
+
+#[test]
+fn test_build_cancellation() {
+    let replacements = replacements();
+    let total = AhoCorasick::new(replacements.clone()).stats().states;
+    // Cancelled while inserting, at the start of the linking, while linking, before compiling, while compiling, and before starting
+    let cancel_steps = [
+        BuildProgress::Inserting { words: 8192 },
+        BuildProgress::Linking { levels: 1, depth: 8 },
+        BuildProgress::Linking { levels: 2, depth: 8 },
+        BuildProgress::Linking { levels: 8, depth: 8 },
+        BuildProgress::Compiling { pass: CompilePass::Rows, states: 4096, total },
+    ];
+    for cancel_at in cancel_steps {
+        let cancellation = CancellationToken::new();
+        let mut last = None;
+        let result = block_on(AhoCorasick::new_async(replacements.clone(), |step| {
+            if step == cancel_at {
+                cancellation.cancel();
+            }
+            last = Some(step);
+        }, cancellation.clone()));
+        assert_eq!(result.unwrap_err(), BuildCancelled);
+        assert_eq!(last, Some(cancel_at)); // Stopped at the next slice
+    }
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    let result = block_on(AhoCorasick::new_async(replacements, |_| panic!("No progress once cancelled"), cancellation));
+    assert_eq!(result.unwrap_err(), BuildCancelled);
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "std")]
+
+use std::{hash::{BuildHasher, Hasher}, sync::Arc, thread};
+use aho_corasick_async::{AhoCorasick, AutomatonCache};
+use test_utils::replace_all;
+
+mod test_utils;
+
+fn replacements(entries: &[(&str, Option<&str>)]) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    entries.iter()
+        .map(|(word, replacement)| (word.as_bytes().to_vec(), replacement.map(|replacement| replacement.as_bytes().to_vec())))
+        .collect()
+}
+
+#[test]
+fn test_cache() {
+    let cache = AutomatonCache::new(2);
+    let builder = AhoCorasick::builder();
+    let first = cache.get_or_build(&builder, replacements(&[("he", Some("she")), ("his", None), ("he", Some("it"))]));
+    // Same dictionary once normalized : other order, replaced duplicate
+    let same = cache.get_or_build(&builder, replacements(&[("he", Some("nobody")), ("his", None), ("he", Some("it"))]));
+    assert!(Arc::ptr_eq(&first.automaton, &same.automaton));
+    assert_eq!(replace_all(&same, b"he his"), b"it his");
+    // Another replacement, or another configuration, is another automaton
+    let other = cache.get_or_build(&builder, replacements(&[("he", Some("she")), ("his", None)]));
+    assert!(!Arc::ptr_eq(&first.automaton, &other.automaton));
+    let dfa = cache.get_or_build(AhoCorasick::builder().dfa(true), replacements(&[("his", None), ("he", Some("it"))]));
+    assert!(!Arc::ptr_eq(&first.automaton, &dfa.automaton));
+    assert_eq!(cache.len(), 2);
+
+    // The least recently used one (first) was evicted
+    let rebuilt = cache.get_or_build(&builder, replacements(&[("his", None), ("he", Some("it"))]));
+    assert!(!Arc::ptr_eq(&first.automaton, &rebuilt.automaton));
+    assert!(rebuilt.to_bytes() == first.to_bytes());
+    let dfa_again = cache.get_or_build(AhoCorasick::builder().dfa(true), replacements(&[("his", None), ("he", Some("it"))]));
+    assert!(Arc::ptr_eq(&dfa.automaton, &dfa_again.automaton));
+
+    cache.clear();
+    assert!(cache.is_empty());
+    let uncached = AutomatonCache::new(0);
+    uncached.get_or_build(&builder, replacements(&[("he", None)]));
+    assert!(uncached.is_empty());
+}
+
+#[test]
+fn test_global_cache() {
+    // Built concurrently by several threads : a single automaton
+    let words: Vec<String> = (0..5000).map(|index| format!("word{}", index)).collect();
+    let automatons: Vec<AhoCorasick> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..8).map(|_| scope.spawn(|| {
+            AhoCorasick::new_cached(words.iter().map(|word| (word.as_bytes().to_vec(), Some(b"*".to_vec()))).collect())
+        })).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    assert!(automatons.iter().all(|ac| Arc::ptr_eq(&ac.automaton, &automatons[0].automaton)));
+    let cached = AhoCorasick::builder().build_cached(words.iter().rev().map(|word| (word.as_bytes().to_vec(), Some(b"*".to_vec()))).collect());
+    assert!(Arc::ptr_eq(&cached.automaton, &automatons[0].automaton));
+    assert_eq!(replace_all(&cached, b"a word42 b"), b"a *2 b"); // word4 is found first
+}
+
+/// Gives the same fingerprint to every dictionary
+#[derive(Debug, Default)]
+struct Colliding;
+
+impl BuildHasher for Colliding {
+    type Hasher = Colliding;
+
+    fn build_hasher(&self) -> Colliding {
+        Colliding
+    }
+}
+
+impl Hasher for Colliding {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, _: &[u8]) {}
+}
+
+#[test]
+fn test_cache_collision() {
+    let cache = AutomatonCache::with_hasher(2, Colliding);
+    let builder = AhoCorasick::builder();
+    let first = cache.get_or_build(&builder, replacements(&[("he", Some("she")), ("his", None)]));
+    // Same fingerprint, another dictionary : built separately
+    let other = cache.get_or_build(&builder, replacements(&[("he", Some("it")), ("his", None)]));
+    assert!(!Arc::ptr_eq(&first.automaton, &other.automaton));
+    assert_eq!(replace_all(&other, b"he his"), b"it his");
+    assert_eq!(cache.len(), 2);
+    // Each dictionary still finds its own automaton
+    let same_other = cache.get_or_build(&builder, replacements(&[("he", Some("it")), ("his", None)]));
+    assert!(Arc::ptr_eq(&other.automaton, &same_other.automaton));
+    let same = cache.get_or_build(&builder, replacements(&[("his", None), ("he", Some("she"))]));
+    assert!(Arc::ptr_eq(&first.automaton, &same.automaton));
+    // A word of one is a prefix of a word of the other
+    let longer = cache.get_or_build(&builder, replacements(&[("he", Some("it")), ("his", None), ("hiss", None)]));
+    assert!(!Arc::ptr_eq(&first.automaton, &longer.automaton) && !Arc::ptr_eq(&other.automaton, &longer.automaton));
+    assert_eq!(cache.len(), 2); // The least recently found one (other) was evicted
+    let rebuilt = cache.get_or_build(&builder, replacements(&[("he", Some("it")), ("his", None)]));
+    assert!(!Arc::ptr_eq(&other.automaton, &rebuilt.automaton));
+    assert_eq!(replace_all(&rebuilt, b"he his"), b"it his");
+}
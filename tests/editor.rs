@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+use std::collections::BTreeMap;
+use aho_corasick_async::{AhoCorasick, AhoCorasickEditor};
+use futures::{executor::block_on, AsyncReadExt};
+use test_utils::replace_all;
+
+mod test_utils;
+
+fn replacements() -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    Vec::from(
+        [
+            ("he".as_bytes().to_vec(), Some("she".as_bytes().to_vec())),
+            ("she".as_bytes().to_vec(), Some("he".as_bytes().to_vec())),
+            ("his".as_bytes().to_vec(), Some("her".as_bytes().to_vec())),
+            ("hers".as_bytes().to_vec(), None),
+        ]
+    )
+}
+
+/// Small deterministic generator, so that the random edits are the same on every run
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % bound as u64) as usize
+    }
+}
+
+#[test]
+fn test_edits_match_fresh_build() {
+    for dfa in [false, true] {
+        let mut rng = Lcg(dfa as u64);
+        let mut editor = AhoCorasick::builder().dfa(dfa).build_editor(replacements());
+        let mut expected: BTreeMap<Vec<u8>, Option<Vec<u8>>> = replacements().into_iter().collect();
+        for step in 0..500 {
+            // Few distinct bytes and short words, so that the words share many prefixes and suffixes
+            let word: Vec<u8> = (0..1 + rng.next(5)).map(|_| b"abcehrs"[rng.next(7)]).collect();
+            if rng.next(3) == 0 {
+                assert_eq!(editor.remove(&word), expected.remove(&word).is_some(), "step {}", step);
+            } else {
+                let replacement = (rng.next(4) != 0).then(|| vec![b'0' + rng.next(10) as u8; rng.next(3)]);
+                editor.insert(word.clone(), replacement.clone());
+                expected.insert(word, replacement);
+            }
+            if step % 10 == 0 {
+                let fresh = AhoCorasick::builder().dfa(dfa).build(expected.clone().into_iter().collect());
+                let published = editor.publish();
+                assert!(published.to_bytes() == fresh.to_bytes(), "step {}", step);
+                assert_eq!(replace_all(&published, b"she sells his hers, abc cabs"), replace_all(&fresh, b"she sells his hers, abc cabs"));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_edits_with_empty_word() {
+    // Root is a word : the states of the first level have it as output link, whether built at once or edited
+    let mut expected = replacements();
+    expected.push((Vec::new(), Some(b"empty".to_vec())));
+    let built = AhoCorasick::new(expected.clone());
+    let mut editor = AhoCorasickEditor::new(Vec::from([(b"he".to_vec(), Some(b"she".to_vec()))]));
+    editor.insert(Vec::new(), Some(b"empty".to_vec()));
+    for (word, replacement) in replacements().into_iter().skip(1) {
+        editor.insert(word, replacement);
+    }
+    let edited = editor.publish();
+    assert_eq!(edited.automaton.to_string(), built.automaton.to_string());
+    assert_eq!(edited.automaton.to_dot(), built.automaton.to_dot());
+    assert!(built.automaton.to_string().lines().nth(1).unwrap().contains("output 0"));
+}
+
+#[test]
+fn test_snapshots() {
+    let mut editor = AhoCorasickEditor::new(replacements());
+    let handle = editor.handle();
+    let mut running = handle.reader("he and she".as_bytes());
+    let mut output = vec![0u8; 4];
+    block_on(running.read_exact(&mut output)).unwrap();
+
+    editor.insert(b"and".to_vec(), Some(b"or".to_vec()));
+    editor.remove(b"she");
+    assert!(editor.has_pending_edits());
+    assert_eq!(replace_all(&handle.snapshot(), b"he and she"), b"she and he");
+    editor.publish();
+    assert!(!editor.has_pending_edits());
+
+    // The running stream keeps the snapshot it was created with, new ones pick up the published edits
+    block_on(running.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, b"she and he");
+    assert_eq!(replace_all(&handle.snapshot(), b"he and she"), b"she or sshe");
+}
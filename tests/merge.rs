@@ -0,0 +1,42 @@
+use aho_corasick_async::{AhoCorasick, ConflictPolicy, MergeConflict};
+use test_utils::replace_all;
+
+mod test_utils;
+
+fn replacements(entries: &[(&str, Option<&str>)]) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    entries.iter()
+        .map(|(word, replacement)| (word.as_bytes().to_vec(), replacement.map(|replacement| replacement.as_bytes().to_vec())))
+        .collect()
+}
+
+#[test]
+fn test_merge() {
+    let left = replacements(&[("he", Some("she")), ("his", Some("her")), ("shed", None), ("same", Some("SAME")), ("", Some("empty"))]);
+    let right = replacements(&[("she", Some("he")), ("his", Some("its")), ("her", Some("his")), ("same", Some("SAME")), ("hers", None)]);
+    // The inputs may have been built with any configuration, the words are the same
+    for (left_ac, right_ac) in [
+        (AhoCorasick::new(left.clone()), AhoCorasick::new(right.clone())),
+        (AhoCorasick::builder().dfa(true).minimize(true).build(left.clone()), AhoCorasick::builder().double_array(true).build(right.clone())),
+    ] {
+        assert_eq!(
+            AhoCorasick::merge(&left_ac, &right_ac, ConflictPolicy::Error).unwrap_err(),
+            MergeConflict { word: b"his".to_vec(), left: Some(b"her".to_vec()), right: Some(b"its".to_vec()) }
+        );
+        // Same as building the union, the last duplicate winning
+        let prefer_left = AhoCorasick::merge(&left_ac, &right_ac, ConflictPolicy::PreferLeft).unwrap();
+        assert!(prefer_left.to_bytes() == AhoCorasick::new([right.clone(), left.clone()].concat()).to_bytes());
+        let prefer_right = AhoCorasick::merge(&left_ac, &right_ac, ConflictPolicy::PreferRight).unwrap();
+        assert!(prefer_right.to_bytes() == AhoCorasick::new([left.clone(), right.clone()].concat()).to_bytes());
+        assert_eq!(replace_all(&prefer_left, b"his hers shed"), b"her shers hed");
+        assert_eq!(replace_all(&prefer_right, b"his hers shed"), b"its shers hed");
+    }
+    // A protected word against a replaced one is a conflict too, identical entries are not
+    let protected = AhoCorasick::new(replacements(&[("same", Some("SAME")), ("she", None)]));
+    let replaced = AhoCorasick::new(replacements(&[("same", Some("SAME")), ("she", Some("he"))]));
+    assert_eq!(AhoCorasick::merge(&protected, &replaced, ConflictPolicy::Error).unwrap_err().left, None);
+    assert!(AhoCorasick::merge(&protected, &protected, ConflictPolicy::Error).unwrap().to_bytes() == protected.to_bytes());
+    // Merging with an empty automaton, with another configuration
+    let empty = AhoCorasick::new(Vec::new());
+    let merged = AhoCorasick::builder().dfa(true).merge(&empty, &replaced, ConflictPolicy::Error).unwrap();
+    assert!(merged.to_bytes() == AhoCorasick::builder().dfa(true).build(replacements(&[("same", Some("SAME")), ("she", Some("he"))])).to_bytes());
+}
@@ -0,0 +1,93 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use aho_corasick_async::AhoCorasick;
+
+/// Counts the bytes currently allocated, and the highest amount reached since the last reset
+struct CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    // Large tables grow in place (without a copy being alive at the same time), so only the difference is counted
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            if new_size >= layout.size() {
+                let current = CURRENT.fetch_add(new_size - layout.size(), Ordering::Relaxed) + new_size - layout.size();
+                PEAK.fetch_max(current, Ordering::Relaxed);
+            } else {
+                CURRENT.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Deterministic words of 4 to 11 lowercase letters, every fourth one having a replacement
+fn words(count: usize) -> impl Iterator<Item = (Vec<u8>, Option<Vec<u8>>)> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..count).map(move |index| {
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        let len = 4 + (next() % 8) as usize;
+        let word: Vec<u8> = (0..len).map(|_| b'a' + (next() % 26) as u8).collect();
+        let replacement = (index % 4 == 0).then(|| word.to_ascii_uppercase());
+        (word, replacement)
+    })
+}
+
+/// Peak of the memory allocated while building, above what was allocated before, and the size of the automaton built
+fn build_peak(build: impl FnOnce() -> AhoCorasick) -> (usize, usize) {
+    let before = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let ac = build();
+    (PEAK.load(Ordering::Relaxed) - before, ac.stats().heap_bytes)
+}
+
+// The only test of this binary : the counters are process-wide, a test running in parallel would skew them
+#[test]
+fn test_peak_memory() {
+    let count = 20_000;
+    let mut sorted: Vec<(Vec<u8>, Option<Vec<u8>>)> = words(count).collect();
+    sorted.sort();
+    // Maximum peak, in percents of the size of the automaton. The trie becomes the state table in place : only the renumbering
+    // tables (8 bytes per node) and the transitions pools come on top of it. Minimize also needs the unminimized tables, and its own
+    // refinement tables (8 bytes per state)
+    let builders = [
+        (AhoCorasick::builder(), 125),
+        (AhoCorasick::builder().double_array(true).clone(), 125),
+        (AhoCorasick::builder().minimize(true).clone(), 150),
+        (AhoCorasick::builder().minimize(true).double_array(true).clone(), 150),
+    ];
+    for (builder, percents) in builders {
+        let (unsorted_peak, heap_bytes) = build_peak(|| builder.build_from_iter(words(count)));
+        assert!(unsorted_peak * 100 <= percents * heap_bytes, "peak {unsorted_peak} for an automaton of {heap_bytes} bytes with {builder:?}");
+        // The words are already in memory here : only the build is measured
+        let (sorted_peak, _) = build_peak(|| builder.build_from_iter(sorted.iter().cloned()));
+        assert!(sorted_peak * 100 <= percents * heap_bytes, "peak {sorted_peak} for sorted words, for an automaton of {heap_bytes} bytes with {builder:?}");
+    }
+}
@@ -0,0 +1,157 @@
+use aho_corasick_async::{AhoCorasick, AhoCorasickBuilder, DeserializeError};
+use test_utils::replace_all;
+
+mod test_utils;
+
+fn replacements() -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    Vec::from(
+        [
+            ("he".as_bytes().to_vec(), Some("she".as_bytes().to_vec())),
+            ("she".as_bytes().to_vec(), Some("he".as_bytes().to_vec())),
+            ("his".as_bytes().to_vec(), Some("her".as_bytes().to_vec())),
+            ("hers".as_bytes().to_vec(), None),
+            ("ab".as_bytes().to_vec(), Some("".as_bytes().to_vec())),
+        ]
+    )
+}
+
+const INPUT: &str = "Now he is here, so is she. his hair is blond, hers is abab";
+
+#[test]
+fn test_roundtrip() {
+    for ac in [
+        AhoCorasick::new(replacements()),
+        AhoCorasick::builder().dfa(true).build(replacements()),
+        AhoCorasick::builder().byte_classes(false).build(replacements()),
+        AhoCorasick::builder().minimize(true).dfa(true).build(replacements()),
+        AhoCorasick::builder().double_array(true).build(replacements()),
+        AhoCorasick::new([replacements(), Vec::from([(Vec::new(), Some(b"empty".to_vec()))])].concat()),
+    ] {
+        let bytes = ac.to_bytes();
+        let loaded = AhoCorasick::from_bytes(&bytes).unwrap();
+        assert_eq!(replace_all(&loaded, INPUT.as_bytes()), replace_all(&ac, INPUT.as_bytes()));
+        assert_eq!(loaded.to_bytes(), bytes);
+    }
+}
+
+#[test]
+fn test_rejected_bytes() {
+    let bytes = AhoCorasick::new(replacements()).to_bytes();
+
+    assert_eq!(AhoCorasick::from_bytes(b"not an automaton").unwrap_err(), DeserializeError::InvalidMagic);
+    assert_eq!(AhoCorasick::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(), DeserializeError::Truncated);
+
+    let mut stale = bytes.clone();
+    stale[8] += 1; // Version
+    assert!(matches!(AhoCorasick::from_bytes(&stale).unwrap_err(), DeserializeError::UnsupportedVersion(_)));
+
+    let mut corrupted = bytes.clone();
+    corrupted[20] ^= 0x10; // State count
+    assert!(AhoCorasick::from_bytes(&corrupted).is_err());
+
+    for corrupted_index in [100, 400, bytes.len() - 1] {
+        let mut corrupted = bytes.clone();
+        corrupted[corrupted_index] ^= 0x10;
+        assert_eq!(AhoCorasick::from_bytes(&corrupted).unwrap_err(), DeserializeError::ChecksumMismatch);
+    }
+}
+
+/// Same bytes with one u32 changed, and the checksum fixed up : only the consistency checks of the tables can reject them
+fn patched(bytes: &[u8], offset: usize, value: u32) -> Vec<u8> {
+    let mut patched = bytes.to_vec();
+    patched[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    let crc = !patched[16..].iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 })
+    });
+    patched[12..16].copy_from_slice(&crc.to_le_bytes());
+    patched
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn test_malformed_tables() {
+    // States of replacements() : 1 "a", 2 "h", 5 "he", 9 "his", 10 "she" (output link to 5), 11 "hers". Each one has 6 u32 fields
+    let bytes = AhoCorasick::new(replacements()).to_bytes();
+    let state = |index: usize, field: usize| 320 + index * 24 + field * 4;
+    let (depth, output_link, word) = (0, 2, 3);
+    assert!(AhoCorasick::from_bytes(&patched(&bytes, state(10, output_link), 5)).is_ok());
+
+    // A depth deeper than the bytes leading to the state
+    assert_eq!(AhoCorasick::from_bytes(&patched(&bytes, state(1, depth), 50)).unwrap_err(), DeserializeError::Malformed("invalid transition depth"));
+    assert_eq!(AhoCorasick::from_bytes(&patched(&bytes, state(11, depth), 3)).unwrap_err(), DeserializeError::Malformed("invalid transition depth"));
+    // Output links to a deeper state, or to a state which is not a word
+    assert_eq!(AhoCorasick::from_bytes(&patched(&bytes, state(10, output_link), 11)).unwrap_err(), DeserializeError::Malformed("invalid output link"));
+    assert_eq!(AhoCorasick::from_bytes(&patched(&bytes, state(10, output_link), 2)).unwrap_err(), DeserializeError::Malformed("invalid output link"));
+    // Two states sharing the same word
+    let shared_word = read_u32(&bytes, state(10, word));
+    assert_eq!(AhoCorasick::from_bytes(&patched(&bytes, state(9, word), shared_word)).unwrap_err(), DeserializeError::Malformed("invalid word"));
+    // Lazy DFA cache larger than any builder can make it, and unknown flags
+    let cache_size = 16 + 9 * 4;
+    assert!(AhoCorasick::from_bytes(&patched(&bytes, cache_size, AhoCorasickBuilder::MAX_LAZY_DFA_CACHE as u32)).is_ok());
+    assert_eq!(
+        AhoCorasick::from_bytes(&patched(&bytes, cache_size, AhoCorasickBuilder::MAX_LAZY_DFA_CACHE as u32 + 1)).unwrap_err(),
+        DeserializeError::Malformed("invalid lazy DFA cache size")
+    );
+    assert_eq!(AhoCorasick::from_bytes(&patched(&bytes, cache_size, u32::MAX)).unwrap_err(), DeserializeError::Malformed("invalid lazy DFA cache size"));
+    assert_eq!(AhoCorasick::from_bytes(&patched(&bytes, 16 + 10 * 4, 2)).unwrap_err(), DeserializeError::Malformed("unknown flags"));
+    // A DFA transition from root straight to a state of depth 4
+    let dfa_bytes = AhoCorasick::builder().dfa(true).build(replacements()).to_bytes();
+    let dfa_start = dfa_bytes.len() - read_u32(&dfa_bytes, 16 + 7 * 4) as usize * 4;
+    assert_eq!(AhoCorasick::from_bytes(&patched(&dfa_bytes, dfa_start, 11)).unwrap_err(), DeserializeError::Malformed("invalid dfa table"));
+}
+
+/// Bytes placed at a chosen alignment in memory, as a memory-mapped file would be
+struct PlacedBytes {
+    buffer: Vec<u8>,
+    offset: usize,
+}
+
+impl PlacedBytes {
+    fn new(bytes: &[u8], misalignment: usize) -> Self {
+        let mut buffer = vec![0u8; bytes.len() + 16];
+        let offset = (8 - buffer.as_ptr() as usize % 8) % 8 + misalignment;
+        buffer[offset..offset + bytes.len()].copy_from_slice(bytes);
+        buffer.truncate(offset + bytes.len());
+        Self { buffer, offset }
+    }
+}
+
+impl AsRef<[u8]> for PlacedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer[self.offset..]
+    }
+}
+
+#[test]
+fn test_shared_bytes() {
+    for ac in [
+        AhoCorasick::new(replacements()),
+        AhoCorasick::builder().dfa(true).build(replacements()),
+    ] {
+        let bytes = ac.to_bytes();
+        let shared = AhoCorasick::from_shared_bytes(PlacedBytes::new(&bytes, 0)).unwrap();
+        assert_eq!(replace_all(&shared, INPUT.as_bytes()), replace_all(&ac, INPUT.as_bytes()));
+        assert_eq!(shared.to_bytes(), bytes);
+
+        assert_eq!(AhoCorasick::from_shared_bytes(PlacedBytes::new(&bytes, 1)).unwrap_err(), DeserializeError::Unaligned);
+        assert_eq!(AhoCorasick::from_shared_bytes(PlacedBytes::new(&bytes[..bytes.len() - 4], 0)).unwrap_err(), DeserializeError::Truncated);
+        let mut corrupted = bytes.clone();
+        corrupted[400] ^= 0x01;
+        assert_eq!(AhoCorasick::from_shared_bytes(PlacedBytes::new(&corrupted, 0)).unwrap_err(), DeserializeError::ChecksumMismatch);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let ac = AhoCorasick::new(replacements());
+    let json = serde_json::to_string(&ac).unwrap();
+    let loaded: AhoCorasick = serde_json::from_str(&json).unwrap();
+    assert_eq!(replace_all(&loaded, INPUT.as_bytes()), replace_all(&ac, INPUT.as_bytes()));
+
+    let corrupted = json.replacen("[65,", "[66,", 1);
+    assert!(serde_json::from_str::<AhoCorasick>(&corrupted).is_err());
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "std")]
+
+use std::{pin::Pin, task::{Context, Poll}};
+use aho_corasick_async::{AhoCorasick, TokenAhoCorasick};
+use futures::{Stream, StreamExt, executor::block_on, stream};
+use test_utils::replace_all;
+
+mod test_utils;
+
+/// Stream answering Pending before every token (for testing repeated calls)
+struct PendingStream<T> {
+    tokens: std::vec::IntoIter<T>,
+    ready: bool,
+}
+
+impl<T: Unpin> Stream for PendingStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        Poll::Ready(self.tokens.next())
+    }
+}
+
+fn replace_tokens<T: Eq + std::hash::Hash + Clone + Unpin>(ac: &TokenAhoCorasick<T>, tokens: Vec<T>) -> Vec<T> {
+    let output: Vec<T> = block_on(ac.clone().into_stream(stream::iter(tokens.clone())).collect());
+    let pending_output: Vec<T> = block_on(ac.clone().into_stream(PendingStream { tokens: tokens.into_iter(), ready: false }).collect());
+    assert!(output == pending_output);
+    output
+}
+
+#[test]
+fn test_bytes_as_tokens() {
+    // With bytes as tokens, the results are the same as with the byte automaton, its specialization
+    let mut seed = 5u32;
+    let mut random = |modulo: u32| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % modulo
+    };
+    for (round, alphabet) in [3u32, 4, 8, 200].into_iter().enumerate() {
+        let word = |random: &mut dyn FnMut(u32) -> u32| (0..1 + random(5)).map(|_| b'a'.wrapping_add(random(alphabet) as u8)).collect::<Vec<u8>>();
+        let mut replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = (0..20)
+            .map(|_| (word(&mut random), (random(4) != 0).then(|| word(&mut random))))
+            .collect();
+        if round == 3 {
+            // All the bytes are symbols
+            replacements.push(((0..=255u8).collect(), None));
+        }
+        let input: Vec<u8> = (0..2000).map(|_| b'a'.wrapping_add(random(alphabet) as u8)).collect();
+        let expected = replace_all(&AhoCorasick::new(replacements.clone()), &input);
+        assert!(replace_tokens(&TokenAhoCorasick::new(replacements), input) == expected, "Alphabet of {}", alphabet);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Event {
+    Connect(u16),
+    Send(String),
+    Ack,
+    Disconnect,
+}
+
+#[test]
+fn test_token_stream() {
+    use Event::*;
+    let ac = TokenAhoCorasick::new(Vec::from([
+        (Vec::from([Send("ping".to_string()), Ack]), Some(Vec::from([Send("pong".to_string())]))),
+        (Vec::from([Connect(80), Disconnect]), Some(Vec::new())),
+        (Vec::from([Connect(443)]), None), // Protected
+        (Vec::from([Connect(443), Disconnect]), Some(Vec::from([Ack]))), // Never replaced, as Connect(443) is found first
+    ]));
+    let input = Vec::from([
+        Connect(80), Send("ping".to_string()), Ack, Send("ping".to_string()), Send("ping".to_string()), Ack,
+        Connect(80), Disconnect, Connect(443), Disconnect, Connect(22), Send("ping".to_string()),
+    ]);
+    assert_eq!(replace_tokens(&ac, input), Vec::from([
+        Connect(80), Send("pong".to_string()), Send("ping".to_string()), Send("pong".to_string()),
+        Connect(443), Disconnect, Connect(22), Send("ping".to_string()),
+    ]));
+    // Word IDs from a tokenizer : many more distinct tokens than bytes
+    let words: Vec<(Vec<u32>, Option<Vec<u32>>)> = (0..20_000u32).map(|id| (Vec::from([id, id + 1]), Some(Vec::from([id * 2])))).collect();
+    let ac = TokenAhoCorasick::new(words);
+    assert_eq!(replace_tokens(&ac, Vec::from([7, 19_999, 20_000, 3, 4, 5, 99_999])), Vec::from([7, 39_998, 6, 5, 99_999]));
+}
@@ -1,11 +1,32 @@
+#![cfg(feature = "std")]
+
 use std::str::from_utf8;
 
-use aho_corasick_async::AhoCorasick;
+use aho_corasick_async::{AcAutomaton, AhoCorasick, AhoCorasickAsyncReader, AhoCorasickAsyncWriter, AutomatonStats, StateId, TraceOutcome, TraceStep};
 use futures::{AsyncReadExt, executor::block_on, AsyncWriteExt};
-use test_utils::{BytesAsyncReader, BytesAsyncWriter};
+use test_utils::{replace_all, BytesAsyncReader, BytesAsyncWriter};
 
 mod test_utils;
 
+/// The same replacements, built with every automaton configuration : they must all give the same output
+fn test_automatons(replacements: &[(Vec<u8>, Option<Vec<u8>>)]) -> Vec<(&'static str, AhoCorasick)> {
+    Vec::from(
+        [
+            ("default", AhoCorasick::new(replacements.to_vec())),
+            ("dfa", AhoCorasick::builder().dfa(true).build(replacements.to_vec())),
+            ("no byte classes", AhoCorasick::builder().byte_classes(false).build(replacements.to_vec())),
+            ("dfa, no byte classes", AhoCorasick::builder().dfa(true).byte_classes(false).build(replacements.to_vec())),
+            ("minimized", AhoCorasick::builder().minimize(true).build(replacements.to_vec())),
+            ("minimized dfa", AhoCorasick::builder().minimize(true).dfa(true).build(replacements.to_vec())),
+            ("double array", AhoCorasick::builder().double_array(true).build(replacements.to_vec())),
+            ("double array, no byte classes", AhoCorasick::builder().double_array(true).byte_classes(false).build(replacements.to_vec())),
+            ("lazy dfa", AhoCorasick::builder().lazy_dfa(1024).build(replacements.to_vec())),
+            ("lazy dfa, tiny cache", AhoCorasick::builder().lazy_dfa(2).double_array(true).build(replacements.to_vec())),
+            ("no prefilter", AhoCorasick::builder().prefilter(false).build(replacements.to_vec())),
+        ]
+    )
+}
+
 #[test]
 fn test_reader() {
     block_on(async {
@@ -74,73 +95,546 @@ fn test_reader() {
                 "'him' is replaced, but not 'she' nor 'ashe'. 'shed' will also not be replaced as 'she' is prioritized".to_owned(),
             ),
         ].iter().enumerate() {
-            // Multiple buffer sizes
-            for test_buffer_size in [1,2,3,5,10,100] {
-                let ac = AhoCorasick::new(replacements.clone());
-
-                let mut buf: Vec<u8> = Vec::with_capacity(test_buffer_size);
-                buf.resize(test_buffer_size, 0u8);
-                println!("Test #{}, buffer size {} ...", test_index, test_buffer_size);
-                {
-                    // Testing the Reader : with and without forced_pending
-                    for forced_pending in [0usize, 2] {
-                        let reader = BytesAsyncReader::new(source_string.as_bytes().to_vec(), forced_pending);
-                        let mut ac_reader = ac.clone().into_reader(reader);
+            // Multiple automaton configurations
+            for (configuration, ac) in test_automatons(replacements) {
+                // Multiple buffer sizes
+                for test_buffer_size in [1,2,3,5,10,100] {
+                    let mut buf: Vec<u8> = vec![0u8; test_buffer_size];
+                    println!("Test #{}, {}, buffer size {} ...", test_index, configuration, test_buffer_size);
+                    {
+                        // Testing the Reader : with and without forced_pending
+                        for forced_pending in [0usize, 2] {
+                            let reader = BytesAsyncReader::new(source_string.as_bytes().to_vec(), forced_pending);
+                            let mut ac_reader = ac.clone().into_reader(reader);
     
-                        let mut output: Vec<u8> = Vec::new();
-                        loop {
-                            match ac_reader.read(&mut buf).await {
-                                Ok(size) => {
-                                    if size == 0 {
-                                        break;
-                                    } else {
-                                        output.extend(&buf[..size]);
-                                    }
-                                },
-                                Err(err) => {
-                                    panic!("BytesAsyncReader error : {}", err)
-                                },
+                            let mut output: Vec<u8> = Vec::new();
+                            loop {
+                                match ac_reader.read(&mut buf).await {
+                                    Ok(size) => {
+                                        if size == 0 {
+                                            break;
+                                        } else {
+                                            output.extend(&buf[..size]);
+                                        }
+                                    },
+                                    Err(err) => {
+                                        panic!("BytesAsyncReader error : {}", err)
+                                    },
+                                }
                             }
+                            assert_eq!(from_utf8(&output).unwrap_or("<utf8 error>"), expected_output);
                         }
-                        assert_eq!(from_utf8(&output).unwrap_or("<utf8 error>"), expected_output);
                     }
-                }
-                {
-                    // Testing the Writer : with and without forced_pending
-                    for forced_pending in [0usize, 2] {
-                        let mut reader = BytesAsyncReader::new(source_string.as_bytes().to_vec(), 0);
-                        let writer = BytesAsyncWriter::new(forced_pending);
-                        let mut ac_writer = ac.clone().into_writer(writer.clone());
+                    {
+                        // Testing the Writer : with and without forced_pending
+                        for forced_pending in [0usize, 2] {
+                            let mut reader = BytesAsyncReader::new(source_string.as_bytes().to_vec(), 0);
+                            let writer = BytesAsyncWriter::new(forced_pending);
+                            let mut ac_writer = ac.clone().into_writer(writer.clone());
     
-                        loop {
-                            match reader.read(&mut buf).await {
-                                Ok(size) => {
-                                    if size == 0 {
-                                        ac_writer.close().await.unwrap();
-                                        break;
-                                    } else {
-                                        ac_writer.write(&buf[..size]).await.unwrap();
-                                    }
-                                },
-                                Err(err) => {
-                                    panic!("BytesAsyncReader error : {}", err)
-                                },
+                            loop {
+                                match reader.read(&mut buf).await {
+                                    Ok(size) => {
+                                        if size == 0 {
+                                            ac_writer.close().await.unwrap();
+                                            break;
+                                        } else {
+                                            ac_writer.write_all(&buf[..size]).await.unwrap();
+                                        }
+                                    },
+                                    Err(err) => {
+                                        panic!("BytesAsyncReader error : {}", err)
+                                    },
+                                }
                             }
+                            assert_eq!(from_utf8(&writer.sink.borrow()).unwrap_or("<utf8 error>"), expected_output);
                         }
-                        assert_eq!(from_utf8(&writer.sink.borrow()).unwrap_or("<utf8 error>"), expected_output);
                     }
-                }
-                {
-                    for forced_pending in [0usize, 2] {
-                        let mut reader = BytesAsyncReader::new(source_string.as_bytes().to_vec(), forced_pending);
-                        let mut writer = BytesAsyncWriter::new(forced_pending);
+                    {
+                        for forced_pending in [0usize, 2] {
+                            let mut reader = BytesAsyncReader::new(source_string.as_bytes().to_vec(), forced_pending);
+                            let mut writer = BytesAsyncWriter::new(forced_pending);
                         
-                        let result = ac.clone().try_stream_replace_all(&mut reader, &mut writer, test_buffer_size).await;
-                        assert!(result.is_ok());
-                        assert_eq!(from_utf8(&writer.sink.borrow()).unwrap_or("<utf8 error>"), expected_output);
+                            let result = ac.clone().try_stream_replace_all(&mut reader, &mut writer, test_buffer_size).await;
+                            assert!(result.is_ok());
+                            assert_eq!(from_utf8(&writer.sink.borrow()).unwrap_or("<utf8 error>"), expected_output);
+                        }
                     }
                 }
             }
         }
     });
 }
+
+#[test]
+fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<AhoCorasick>();
+    assert_send_sync::<AhoCorasickAsyncReader<BytesAsyncReader>>();
+    assert_send_sync::<AhoCorasickAsyncWriter<futures::io::Cursor<Vec<u8>>>>();
+
+    // One automaton shared by streams running on several threads
+    let ac = AhoCorasick::new(Vec::from(
+        [
+            ("he".as_bytes().to_vec(), Some("she".as_bytes().to_vec())),
+            ("she".as_bytes().to_vec(), Some("he".as_bytes().to_vec())),
+        ]
+    ));
+    let handles: Vec<_> = (0..4).map(|_| {
+        let ac = ac.clone();
+        std::thread::spawn(move || {
+            block_on(async {
+                let reader = BytesAsyncReader::new("so is she, and he is here".as_bytes().to_vec(), 0);
+                let mut output = String::new();
+                ac.into_reader(reader).read_to_string(&mut output).await.unwrap();
+                output
+            })
+        })
+    }).collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), "so is he, and she is shere");
+    }
+}
+
+#[test]
+fn test_dense_rows() {
+    block_on(async {
+        // 'x' gets a child for every letter, which is above the dense row threshold
+        let letters: Vec<u8> = (b'a'..=b'z').chain(b'A'..=b'Z').collect();
+        let replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = letters.iter()
+            .map(|letter| (vec![b'x', *letter], Some(vec![*letter, *letter])))
+            .collect();
+        let ac = AhoCorasick::new(replacements);
+        let reader = BytesAsyncReader::new("xa-xZ-x-xyzx".as_bytes().to_vec(), 0);
+        let mut output = String::new();
+        ac.into_reader(reader).read_to_string(&mut output).await.unwrap();
+        assert_eq!(output, "aa-ZZ-x-yyzx");
+    });
+}
+
+#[test]
+fn test_minimize() {
+    // Inflections of the same stems share their endings, which become the same states once minimized
+    let mut replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = Vec::new();
+    for stem in ["walk", "talk", "jump", "park", "mark", "bark"] {
+        for ending in ["", "s", "ed", "ing", "ings"] {
+            replacements.push((format!("{}{}", stem, ending).into_bytes(), Some(b"<verb>".to_vec())));
+        }
+        replacements.push((format!("{}er", stem).into_bytes(), None));
+    }
+    replacements.push((b"king".to_vec(), Some(b"<king>".to_vec())));
+    let input = b"walking talks, the walker parked by the barking king. jumpings marks a parking talke kingdom";
+    for dfa in [false, true] {
+        let ac = AhoCorasick::builder().dfa(dfa).build(replacements.clone());
+        let minimized = AhoCorasick::builder().dfa(dfa).minimize(true).build(replacements.clone());
+        assert_eq!(ac.states_removed(), 0);
+        assert!(minimized.states_removed() > 0);
+        assert!(minimized.to_bytes().len() < ac.to_bytes().len());
+        assert_eq!(replace_all(&minimized, input), replace_all(&ac, input));
+        assert_eq!(AhoCorasick::from_bytes(&minimized.to_bytes()).unwrap().states_removed(), minimized.states_removed());
+    }
+}
+
+#[test]
+fn test_double_array() {
+    // Many distinct bytes (UTF-8 encoded Japanese), sharing prefixes and suffixes
+    let replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = [
+        ("東京タワー", Some("Tokyo Tower")),
+        ("東京", Some("Tokyo")),
+        ("京都タワー", Some("Kyoto Tower")),
+        ("京都", Some("Kyoto")),
+        ("タワーマンション", None),
+        ("緑茶ペットボトル", Some("green tea")),
+        ("ペット", Some("pet")),
+        ("ボトル", Some("bottle")),
+        ("茶", Some("tea")),
+    ].into_iter().map(|(word, replacement)| (word.as_bytes().to_vec(), replacement.map(|replacement| replacement.as_bytes().to_vec()))).collect();
+    let input = "東京タワーと京都タワー、東京のタワーマンションで緑茶ペットボトルとペットと茶。東京都".as_bytes();
+    let expected = replace_all(&AhoCorasick::new(replacements.clone()), input);
+    for minimize in [false, true] {
+        for dfa in [false, true] {
+            let ac = AhoCorasick::builder().double_array(true).minimize(minimize).dfa(dfa).build(replacements.clone());
+            assert!(replace_all(&ac, input) == expected, "minimize {}, dfa {}", minimize, dfa);
+            let loaded = AhoCorasick::from_bytes(&ac.to_bytes()).unwrap();
+            assert!(replace_all(&loaded, input) == expected, "minimize {}, dfa {}", minimize, dfa);
+        }
+    }
+}
+
+#[test]
+fn test_lazy_dfa() {
+    block_on(async {
+        let replacements = Vec::from([
+            ("he".as_bytes().to_vec(), Some("she".as_bytes().to_vec())),
+            ("she".as_bytes().to_vec(), Some("he".as_bytes().to_vec())),
+            ("hers".as_bytes().to_vec(), None),
+        ]);
+        let input = "she said he is hers, ".repeat(50);
+        for cache_size in [1, 16, 1024] {
+            let ac = AhoCorasick::builder().lazy_dfa(cache_size).build(replacements.clone());
+            let mut reader = ac.into_reader(BytesAsyncReader::new(input.as_bytes().to_vec(), 0));
+            let mut output = String::new();
+            reader.read_to_string(&mut output).await.unwrap();
+            assert_eq!(output, "he said she is shers, ".repeat(50));
+            // Every input byte not skipped by the prefilter is one transition, either cached or resolved
+            let stats = reader.lazy_dfa_stats().unwrap();
+            assert!(stats.hits + stats.misses > 0 && stats.hits + stats.misses <= input.len() as u64, "{:?}", stats);
+            if cache_size == 1024 {
+                // The input repeats the same transitions, which are only resolved the first time
+                assert!(stats.misses <= 30, "{:?}", stats);
+            }
+        }
+        let ac = AhoCorasick::builder().dfa(true).lazy_dfa(1024).build(replacements.clone());
+        assert_eq!(ac.into_writer(BytesAsyncWriter::new(0)).lazy_dfa_stats(), None);
+    });
+    // The size is capped, so that the automaton can still be loaded back
+    let ac = AhoCorasick::builder().lazy_dfa(usize::MAX).build(Vec::from([("he".as_bytes().to_vec(), Some("she".as_bytes().to_vec()))]));
+    let loaded = AhoCorasick::from_bytes(&ac.to_bytes()).unwrap();
+    assert_eq!(replace_all(&loaded, b"he said"), b"she said");
+}
+
+#[test]
+fn test_long_patterns() {
+    // A small stack, so that any recursion over the bytes of a pattern or over the suffix links would overflow it
+    std::thread::Builder::new().stack_size(256 * 1024).spawn(|| {
+        const LEN: usize = 200_000;
+        // Every state of the long pattern links to the previous one : the deepest suffix chain possible
+        let signature = [vec![b'a'; LEN], b"b".to_vec()].concat();
+        let paragraph: Vec<u8> = (0..LEN).map(|index| b"lorem ipsum "[index % 12]).collect();
+        let replacements = Vec::from([
+            (signature, Some(b"<signature>".to_vec())),
+            (paragraph.clone(), Some(Vec::new())),
+            (b"ac".to_vec(), Some(b"<ac>".to_vec())),
+        ]);
+        let input = [
+            vec![b'a'; LEN + 10], b"b-".to_vec(),
+            vec![b'a'; LEN], b"c-".to_vec(), // Fails at the deepest state, and follows the whole chain down to "a"
+            paragraph.clone(), b"-".to_vec(),
+            paragraph[..LEN - 1].to_vec(),
+        ].concat();
+        let expected = [
+            vec![b'a'; 10], b"<signature>-".to_vec(),
+            vec![b'a'; LEN - 1], b"<ac>-".to_vec(),
+            b"-".to_vec(),
+            paragraph[..LEN - 1].to_vec(),
+        ].concat();
+        // Without byte classes, a full DFA of this size would need hundreds of megabytes, so only the compact configurations are checked
+        for (name, ac) in [
+            ("default", AhoCorasick::new(replacements.clone())),
+            ("dfa", AhoCorasick::builder().dfa(true).build(replacements.clone())),
+            ("minimized", AhoCorasick::builder().minimize(true).build(replacements.clone())),
+            ("double array", AhoCorasick::builder().double_array(true).build(replacements.clone())),
+            ("lazy dfa", AhoCorasick::builder().lazy_dfa(1024).build(replacements.clone())),
+        ] {
+            assert!(replace_all(&ac, &input) == expected, "{}", name);
+        }
+        assert!(AhoCorasick::new(replacements).automaton.to_string().len() > 2 * LEN);
+    }).unwrap().join().unwrap();
+}
+
+#[test]
+fn test_display() {
+    let ac = AhoCorasick::new(Vec::from([
+        (b"he".to_vec(), None),
+        (b"hi".to_vec(), None),
+        (b"s".to_vec(), None),
+    ]));
+    assert_eq!(ac.automaton.to_string(), [
+        "0: depth 0, suffix 0 [h => 1, s => 2]",
+        "1: depth 1, suffix 0 [e => 3, i => 4]",
+        "2: depth 1, suffix 0, protected word []",
+        "3: depth 2, suffix 0, protected word []",
+        "4: depth 2, suffix 0, protected word []",
+    ].join("\n"));
+
+    // Links, escaped bytes and replacements. The dump does not depend on how the transitions are stored
+    let replacements = Vec::from([
+        (b"he".to_vec(), Some(b"she \"x\"".to_vec())),
+        (b"she".to_vec(), None),
+        (b"e\n".to_vec(), Some(b"\\".to_vec())),
+    ]);
+    let expected = [
+        "0: depth 0, suffix 0 [e => 1, h => 2, s => 3]",
+        "1: depth 1, suffix 0 [\\n => 4]",
+        "2: depth 1, suffix 0 [e => 5]",
+        "3: depth 1, suffix 0 [h => 6]",
+        "4: depth 2, suffix 0, word => \"\\\\\" []",
+        "5: depth 2, suffix 1, word => \"she \\\"x\\\"\" []",
+        "6: depth 2, suffix 2 [e => 7]",
+        "7: depth 3, suffix 5, output 5, protected word []",
+    ].join("\n");
+    for (configuration, ac) in test_automatons(&replacements) {
+        if !configuration.contains("minimized") {
+            assert_eq!(ac.automaton.to_string(), expected, "{}", configuration);
+        }
+    }
+    assert_eq!(AhoCorasick::new(replacements.clone()).automaton.to_dot(), r#"digraph automaton {
+    rankdir=LR;
+    node [shape=circle];
+    4 [shape=doublecircle, label="4\n=> \"\\\\\""];
+    5 [shape=doublecircle, label="5\n=> \"she \\\"x\\\"\""];
+    7 [shape=doublecircle, label="7\nprotected"];
+    0 -> 1 [label="e"];
+    0 -> 2 [label="h"];
+    0 -> 3 [label="s"];
+    1 -> 4 [label="\\n"];
+    2 -> 5 [label="e"];
+    3 -> 6 [label="h"];
+    5 -> 1 [style=dashed, color=blue];
+    6 -> 7 [label="e"];
+    6 -> 2 [style=dashed, color=blue];
+    7 -> 5 [style=dashed, color=blue];
+    7 -> 5 [style=dotted, color=red];
+}
+"#);
+}
+
+#[test]
+fn test_stats() {
+    let replacements = Vec::from([
+        (b"he".to_vec(), Some(b"she \"x\"".to_vec())),
+        (b"she".to_vec(), None),
+        (b"e\n".to_vec(), Some(b"\\".to_vec())),
+    ]);
+    // The structure does not depend on how the transitions are stored, only the memory does
+    for (configuration, ac) in test_automatons(&replacements) {
+        let stats = ac.stats();
+        assert_eq!(
+            AutomatonStats { heap_bytes: 0, ..stats },
+            AutomatonStats { states: 8, patterns: 3, max_depth: 3, transitions: 7, replacement_bytes: 8, heap_bytes: 0 },
+            "{}", configuration
+        );
+        assert!(stats.heap_bytes > std::mem::size_of::<AcAutomaton>(), "{}", configuration);
+    }
+    let ac = AhoCorasick::new(replacements.clone());
+    let dfa = AhoCorasick::builder().dfa(true).build(replacements);
+    assert!(dfa.stats().heap_bytes > ac.stats().heap_bytes);
+    // Used in place : the tables belong to the shared bytes
+    let shared = AhoCorasick::from_shared_bytes(ac.to_bytes()).unwrap();
+    assert_eq!(AutomatonStats { heap_bytes: 0, ..shared.stats() }, AutomatonStats { heap_bytes: 0, ..ac.stats() });
+    assert!(shared.stats().heap_bytes < ac.stats().heap_bytes);
+    assert_eq!(AhoCorasick::new(Vec::new()).stats().max_depth, 0);
+}
+
+#[test]
+fn test_trace() {
+    let replacements = Vec::from([
+        (b"hers".to_vec(), Some(b"X".to_vec())),
+        (b"his".to_vec(), Some(b"her".to_vec())),
+        (b"she".to_vec(), None),
+    ]);
+    let trace = AhoCorasick::new(replacements.clone()).trace(b"hhisshe\n");
+    assert_eq!(trace.steps[1], TraceStep {
+        position: 1, byte: b'h', from: trace.steps[0].to, to: trace.steps[0].to, suffix_links: 1, depth: 1, flushed: 1, outcome: TraceOutcome::Pending,
+    });
+    assert_eq!(trace.steps[3].outcome, TraceOutcome::Replaced(b"her".to_vec()));
+    assert_eq!(trace.steps[6].outcome, TraceOutcome::Protected);
+    assert_eq!(trace.to_string(), [
+        "position byte     from     to  links  depth  flushed  outcome",
+        "       0 h           0      1      0      1        0",
+        "       1 h           1      1      1      1        1",
+        "       2 i           1      4      0      2        0",
+        "       3 s           4      7      0      3        0  replaced by \"her\", reset",
+        "       4 s           0      2      0      1        0",
+        "       5 h           2      5      0      2        0",
+        "       6 e           5      8      0      3        0  protected word, reset",
+        "       7 \\n          0      0      0      0        1",
+        "end : 0 bytes flushed",
+        "output : \"hhershe\\n\"",
+    ].join("\n"));
+
+    // Same output as the streams with every configuration, including a potential match left at the end
+    let input = b"ushers his hershe hers hishe";
+    for (configuration, ac) in test_automatons(&replacements) {
+        let trace = ac.trace(input);
+        assert_eq!(trace.output, replace_all(&ac, input), "{}", configuration);
+        assert_eq!(trace.flushed_at_end, 2, "{}", configuration);
+        assert_eq!(trace.steps.len(), input.len(), "{}", configuration);
+    }
+}
+
+#[test]
+fn test_build_from_iter() {
+    // Words generated in sorted order, as read from a dictionary file, with a duplicated word whose last replacement must win
+    let words = || (0..2000u32).map(|index| (format!("word{:05}", index * 7).into_bytes(), Some(format!("<{}>", index).into_bytes())))
+        .chain([(b"word00007".to_vec(), Some(b"<last>".to_vec()))]);
+    let sorted = AhoCorasick::builder().build_from_iter(words());
+    let mut shuffled: Vec<(Vec<u8>, Option<Vec<u8>>)> = words().collect();
+    let last = shuffled.pop().unwrap();
+    shuffled.reverse();
+    shuffled.push(last);
+    let unsorted = AhoCorasick::new(shuffled);
+    assert!(sorted.to_bytes() == unsorted.to_bytes());
+    assert_eq!(replace_all(&sorted, b"word00000 word00007 word13993 word13994"), b"<0> <last> <1999> word13994");
+}
+
+#[test]
+fn test_prefilter() {
+    let input = b"<p>Some text &amp; [links](here), {{ templates }} and plain words, no match at all here. <b>&lt;</b> zzz";
+    for words in [
+        Vec::from(["zzz"]), // memchr
+        Vec::from(["&amp;", "<b>"]), // memchr2
+        Vec::from(["&amp;", "<b>", "{{"]), // memchr3
+        Vec::from(["&amp;", "&lt;", "<b>", "{{", "[links]", "zzz"]), // Rare start bytes : byte set
+        Vec::from(["text", "and", "words", "here", "no", "match", "plain"]), // Common start bytes : no prefilter
+        Vec::from(["here", "e"]), // Two start bytes, one of them being the start of the other word too
+        Vec::new(), // No start byte at all
+    ] {
+        let replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = words.iter()
+            .map(|word| (word.as_bytes().to_vec(), Some(format!("({})", word.len()).into_bytes())))
+            .collect();
+        let expected = replace_all(&AhoCorasick::builder().prefilter(false).build(replacements.clone()), input);
+        for (name, ac) in test_automatons(&replacements) {
+            assert!(replace_all(&ac, input) == expected, "{:?} {}", words, name);
+        }
+    }
+}
+
+#[test]
+fn test_packed_search() {
+    // Few words of at least 2 bytes : packed search of their first bytes, with the automaton walked from the candidates only
+    let words = ["Host", "Cookie", "Set-Cookie", "X-Token", "token=", "Bearer ", "id", "ids", "if-", "secret"];
+    let replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = words.iter()
+        .enumerate()
+        .map(|(index, word)| (word.as_bytes().to_vec(), (index % 3 != 0).then(|| format!("<{}>", index).into_bytes())))
+        .collect();
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("ssse3") {
+        assert!(format!("{:?}", AhoCorasick::new(replacements.clone()).automaton).contains("Teddy"));
+    }
+    // Near misses of the prefixes everywhere, words straddling the vector boundaries, and a word at the very end
+    let mut input = Vec::new();
+    let mut seed = 7u32;
+    for _ in 0..3000 {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let piece: &[u8] = match (seed >> 16) % 12 {
+            0 => b"Hos", 1 => b"Cook", 2 => b"Set-", 3 => b"X-Tok", 4 => b"token", 5 => b"Bearer", 6 => b"i", 7 => b"d",
+            8 => b"Host", 9 => b"ids", 10 => b"secre", _ => b" plain text: ",
+        };
+        input.extend_from_slice(piece);
+    }
+    input.extend_from_slice(b"secret");
+    let expected = replace_all(&AhoCorasick::builder().prefilter(false).build(replacements.clone()), &input);
+    for (name, ac) in test_automatons(&replacements) {
+        assert!(replace_all(&ac, &input) == expected, "{}", name);
+    }
+}
+
+#[test]
+fn test_chunk_boundaries() {
+    // Long words spanning many chunks, partial matches falling back through suffix links, protected words, and replacements
+    // much longer than the words (overflowing the reader's buffer) : the output must not depend on where the chunks are cut
+    let replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = Vec::from([
+        ("abcabcabcabcabcd".as_bytes().to_vec(), Some("<long>".as_bytes().to_vec())),
+        ("bcab".as_bytes().to_vec(), Some("#".repeat(40).into_bytes())),
+        ("cabx".as_bytes().to_vec(), None),
+        ("xa".as_bytes().to_vec(), Some("".as_bytes().to_vec())),
+        ("d".as_bytes().to_vec(), Some("D".as_bytes().to_vec())),
+    ]);
+    let mut input = Vec::new();
+    let mut seed = 11u32;
+    for _ in 0..400 {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let piece: &[u8] = match (seed >> 16) % 8 {
+            0 => b"abcabcabcabcabcd", 1 => b"abcabcabcab", 2 => b"cabx", 3 => b"xa", 4 => b"bca", 5 => b"d", 6 => b"ab", _ => b" -- ",
+        };
+        input.extend_from_slice(piece);
+    }
+    for (configuration, ac) in test_automatons(&replacements) {
+        let expected = replace_all(&ac, &input);
+        block_on(async {
+            for chunk_size in (1..=17).chain([4096]) {
+                let mut ac_reader = ac.clone().into_reader(BytesAsyncReader::new(input.clone(), 0));
+                let mut output = Vec::new();
+                let mut buf = vec![0u8; chunk_size];
+                loop {
+                    match ac_reader.read(&mut buf).await.unwrap() {
+                        0 => break,
+                        size => output.extend_from_slice(&buf[..size]),
+                    }
+                }
+                assert!(output == expected, "Reader, {}, chunk size {}", configuration, chunk_size);
+                let writer = BytesAsyncWriter::new(0);
+                let mut ac_writer = ac.clone().into_writer(writer.clone());
+                for chunk in input.chunks(chunk_size) {
+                    ac_writer.write_all(chunk).await.unwrap();
+                }
+                ac_writer.close().await.unwrap();
+                assert!(*writer.sink.borrow() == expected, "Writer, {}, chunk size {}", configuration, chunk_size);
+            }
+        });
+    }
+}
+
+#[test]
+fn test_replacer() {
+    // The sans-I/O core, fed by hand : the output must be the same as the streams', wherever the chunks are cut
+    let replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = Vec::from([
+        ("abcabcd".as_bytes().to_vec(), Some("<long>".as_bytes().to_vec())),
+        ("bcab".as_bytes().to_vec(), Some("#".as_bytes().to_vec())),
+        ("cabx".as_bytes().to_vec(), None),
+        ("d".as_bytes().to_vec(), Some("D".as_bytes().to_vec())),
+    ]);
+    let input = "abcabcd abcabcab cabx abcabcabcd dabcab".repeat(8).into_bytes();
+    for (configuration, ac) in test_automatons(&replacements) {
+        let expected = replace_all(&ac, &input);
+        for chunk_size in 1..=9 {
+            let mut replacer = ac.clone().into_replacer();
+            let mut output = Vec::new();
+            for chunk in input.chunks(chunk_size) {
+                replacer.replace(chunk, &mut output);
+                assert!(replacer.potential().len() < "abcabcd".len(), "{}, chunk size {}", configuration, chunk_size);
+            }
+            replacer.finish(&mut output);
+            assert!(output == expected, "{}, chunk size {}", configuration, chunk_size);
+        }
+    }
+}
+
+/// Same replacements as the streams, driven through the low-level API
+fn replace_with_states(automaton: &AcAutomaton, input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut state = automaton.start_state();
+    let mut potential_start = 0; // The last match_len bytes are held back
+    for (position, byte) in input.iter().enumerate() {
+        state = automaton.next_state(state, *byte);
+        let match_start = position + 1 - automaton.match_len(state);
+        output.extend_from_slice(&input[potential_start..match_start]);
+        potential_start = match_start;
+        if automaton.is_match(state) {
+            match automaton.replacement(state) {
+                Some(replacement) => output.extend_from_slice(replacement),
+                None => output.extend_from_slice(&input[match_start..=position]), // Protected word
+            }
+            potential_start = position + 1;
+            state = automaton.start_state();
+        }
+    }
+    output.extend_from_slice(&input[potential_start..]);
+    output
+}
+
+#[test]
+fn test_state_machine() {
+    let replacements: Vec<(Vec<u8>, Option<Vec<u8>>)> = Vec::from([
+        ("he".as_bytes().to_vec(), Some("she".as_bytes().to_vec())),
+        ("she".as_bytes().to_vec(), Some("he".as_bytes().to_vec())),
+        ("his".as_bytes().to_vec(), Some("her".as_bytes().to_vec())),
+        ("hers".as_bytes().to_vec(), Some("his".as_bytes().to_vec())),
+        ("shed".as_bytes().to_vec(), None),
+        ("aaaa".as_bytes().to_vec(), Some("".as_bytes().to_vec())),
+    ]);
+    let input = "Now he is here, so is she. his hair is blond, hers is big, the shed is aaaaaaaaaa".as_bytes();
+    for (configuration, ac) in test_automatons(&replacements) {
+        let automaton = &ac.automaton;
+        assert!(!automaton.is_match(automaton.start_state()));
+        assert_eq!(replace_with_states(automaton, input), replace_all(&ac, input), "{}", configuration);
+        // Any number of states walk the same automaton at once
+        let mut states: [StateId; 2] = [automaton.start_state(); 2];
+        for (left, right) in b"xsher".iter().zip(b"hishe".iter()) {
+            states = [automaton.next_state(states[0], *left), automaton.next_state(states[1], *right)];
+        }
+        assert!(!automaton.is_match(states[0]) && automaton.match_len(states[0]) == 3); // "her", the beginning of "hers"
+        assert!(automaton.is_match(states[1]) && automaton.replacement(states[1]) == Some(b"he"));
+    }
+}
@@ -1,6 +1,30 @@
+#![allow(dead_code)] // Each test file only uses some of the utilities
 use std::{task::Poll, rc::Rc, cell::RefCell};
+use aho_corasick_async::AhoCorasick;
 use futures::{AsyncRead, AsyncWrite};
 
+/// Performs the replacements on the whole input at once, through the reader
+#[cfg(feature = "std")]
+pub fn replace_all(ac: &AhoCorasick, input: &[u8]) -> Vec<u8> {
+    futures::executor::block_on(async {
+        let mut reader = BytesAsyncReader::new(input.to_vec(), 0);
+        let mut writer = BytesAsyncWriter::new(0);
+        ac.clone().try_stream_replace_all(&mut reader, &mut writer, 64).await.unwrap();
+        let output = writer.sink.borrow().clone();
+        output
+    })
+}
+
+/// Performs the replacements on the whole input at once, through the Replacer : the reader needs the std feature
+#[cfg(not(feature = "std"))]
+pub fn replace_all(ac: &AhoCorasick, input: &[u8]) -> Vec<u8> {
+    let mut replacer = aho_corasick_async::Replacer::new(ac.clone());
+    let mut output = Vec::new();
+    replacer.replace(input, &mut output);
+    replacer.finish(&mut output);
+    output
+}
+
 /// Simple AsyncReader from a Vec<u8>
 pub struct BytesAsyncReader {
     source: Vec<u8>,